@@ -0,0 +1,39 @@
+#!/bin/sh
+# Sets up a local two-node Erbium testnet under ./testnet. Every node gets the
+# same genesis, so rerunning with the same parameters yields the same network.
+set -e
+
+CHAIN_ID=${CHAIN_ID:-7714}
+TIMESTAMP=${TIMESTAMP:-1728151993}
+BLOCK_TIME=${BLOCK_TIME:-10}
+STAKE=${STAKE:-100000}
+NODES="1 2"
+
+cargo build --release
+BIN="$(pwd)/target/release/erbium-node"
+
+# Each node needs its own key before the genesis can list them as validators
+VALIDATORS=""
+for i in $NODES; do
+    dir="testnet/node$i"
+    if [ -d "$dir/database" ]; then
+        echo "$dir is already initialized, remove ./testnet to start over" >&2
+        exit 1
+    fi
+    mkdir -p "$dir/config"
+    public_key=$(cd "$dir" && "$BIN" keygen | awk '/Public key:/ { print $3 }')
+    VALIDATORS="$VALIDATORS --validator $public_key:$STAKE"
+done
+
+for i in $NODES; do
+    dir="testnet/node$i"
+    port=$((8007 + i))
+    peer=$((8010 - i))
+    cat > "$dir/config/network.toml" <<TOML
+listen_address = "127.0.0.1:$port"
+bootstrap_nodes = ["127.0.0.1:$peer"]
+TOML
+    (cd "$dir" && "$BIN" init --chain-id "$CHAIN_ID" --timestamp "$TIMESTAMP" --block-time "$BLOCK_TIME" $VALIDATORS)
+done
+
+echo "Testnet ready. Start each node with: (cd testnet/nodeN && $BIN)"
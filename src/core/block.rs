@@ -1,6 +1,12 @@
 // src/core/block.rs
 
+use crate::core::consensus::vote::CommitCertificate;
+use crate::core::genesis;
 use crate::core::transaction::Transaction;
+use crate::crypto::hash::calculate_hash;
+use crate::crypto::keys::KeyPair;
+use crate::crypto::merkle::{self, MerkleProof};
+use crate::crypto::signature;
 use serde::{Deserialize, Serialize};
 
 // THE FIX IS HERE: We need to add `, Clone` to this line.
@@ -10,7 +16,36 @@ pub struct Header {
     pub prev_block_hash: String,
     pub timestamp: u64,
     pub merkle_root: String,
+    pub receipts_root: String,
+    /// Root of the state after applying this block, see `State::state_root`
+    pub state_root: String,
+    /// Fee every transaction in this block burns, see `consensus::fees`
+    pub base_fee: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
     pub nonce: u32,
+    /// Validator that produced the block, empty for the genesis block
+    pub proposer: String,
+    /// Consensus round the block was produced in. With the parent hash it seeds proposer selection.
+    pub round: u32,
+    /// The proposer's signature over `signing_hash`
+    pub signature: String,
+}
+
+impl Header {
+    /// Digest the proposer signs: every header field but the signature, and the chain ID
+    pub fn signing_hash(&self) -> String {
+        let unsigned = Header {
+            signature: String::new(),
+            ..self.clone()
+        };
+        calculate_hash(&(genesis::chain_id(), unsigned))
+    }
+
+    /// Checks that the header is signed by the proposer it names
+    pub fn verify_signature(&self) -> bool {
+        signature::verify_signature(&self.signing_hash(), &self.signature, &self.proposer)
+    }
 }
 
 // AND THE FIX IS HERE: We also add `, Clone` to this line.
@@ -18,11 +53,22 @@ pub struct Header {
 pub struct Block {
     pub header: Header,
     pub transactions: Vec<Transaction>,
+    /// Precommits that made the block final, attached once it is committed. It is not part
+    /// of the block hash the precommits sign. Only the genesis block is final without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitCertificate>,
+}
+
+// What a block's hash and size cover: everything but its commit certificate
+#[derive(Serialize)]
+struct BlockContents<'a> {
+    header: &'a Header,
+    transactions: &'a [Transaction],
 }
 
 impl Block {
     // This is a general-purpose constructor for new blocks.
-    pub fn new(block_number: u64, prev_block_hash: String, transactions: Vec<Transaction>) -> Self {
+    pub fn new(block_number: u64, prev_block_hash: String, base_fee: u64, transactions: Vec<Transaction>) -> Self {
         let mut block = Block {
             header: Header {
                 block_number,
@@ -31,39 +77,77 @@ impl Block {
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
                     .as_secs(),
-                merkle_root: String::new(), // Placeholder for now
+                merkle_root: String::new(), // Filled in below
+                receipts_root: merkle::EMPTY_ROOT.to_string(), // Set once the transactions have been executed
+                state_root: merkle::EMPTY_ROOT.to_string(), // Same
+                base_fee,
+                gas_limit: genesis::block_gas_limit(),
+                gas_used: Self::total_gas(&transactions),
                 nonce: 0,
+                proposer: String::new(), // Set by the producer before signing
+                round: 0,
+                signature: String::new(),
             },
             transactions,
+            commit: None,
         };
 
-        // We will calculate a real Merkle root later. For now, let's hash the transactions.
-        block.header.merkle_root = crate::crypto::hash::calculate_hash(&block.transactions);
+        block.header.merkle_root = Self::compute_merkle_root(&block.transactions);
         block
     }
 
     // Creates the very first block of the chain, the Genesis Block.
     pub fn create_genesis_block() -> Self {
-        let genesis_header = Header {
-            block_number: 0,
-            // The genesis block has no previous block, so the hash is just "0".
-            prev_block_hash: "0".to_string(),
-            // Using a fixed timestamp for reproducibility.
-            timestamp: 1728151993, // Represents 2025-10-05 14:13:13 UTC
-            merkle_root: "0".to_string(),
-            nonce: 0,
-        };
+        // The genesis block is defined by the network's genesis spec
+        genesis::get_genesis_block()
+    }
+
+    // Signs the header as its proposer. Must be the last change to the header.
+    pub fn sign(&mut self, keypair: &KeyPair) {
+        self.header.proposer = keypair.get_address();
+        self.header.signature = signature::sign_hash(&self.header.signing_hash(), keypair);
+    }
 
-        Block {
-            header: genesis_header,
-            // The genesis block has no transactions.
-            transactions: Vec::new(),
+    // Computes the Merkle root over the hashes of the given transactions.
+    pub fn compute_merkle_root(transactions: &[Transaction]) -> String {
+        merkle::merkle_root(&Self::transaction_hashes(transactions))
+    }
+
+    // Builds a proof that the transaction at `index` is included in this block.
+    pub fn transaction_proof(&self, index: usize) -> Option<MerkleProof> {
+        merkle::generate_proof(&Self::transaction_hashes(&self.transactions), index)
+    }
+
+    // Checks a proof produced by `transaction_proof` against a block's merkle root.
+    pub fn verify_transaction_proof(tx: &Transaction, proof: &MerkleProof, merkle_root: &str) -> bool {
+        merkle::verify_proof(&tx.hash(), proof, merkle_root)
+    }
+
+    // Sums the gas of the given transactions.
+    pub fn total_gas(transactions: &[Transaction]) -> u64 {
+        transactions.iter().map(|tx| tx.gas()).sum()
+    }
+
+    // Size of the block serialized as JSON, as it is sent to peers, without the commit
+    // certificate that is added after the proposer built it.
+    pub fn size(&self) -> usize {
+        serde_json::to_string(&self.contents()).map_or(usize::MAX, |json| json.len())
+    }
+
+    fn contents(&self) -> BlockContents<'_> {
+        BlockContents {
+            header: &self.header,
+            transactions: &self.transactions,
         }
     }
 
-    // Calculates and returns the SHA-256 hash of the entire block.
+    fn transaction_hashes(transactions: &[Transaction]) -> Vec<String> {
+        transactions.iter().map(|tx| tx.hash()).collect()
+    }
+
+    // Calculates and returns the SHA-256 hash of the block, leaving out its commit certificate.
     pub fn calculate_hash(&self) -> String {
         // We use our generic hash function from the crypto module.
-        crate::crypto::hash::calculate_hash(self)
+        crate::crypto::hash::calculate_hash(&self.contents())
     }
 }
\ No newline at end of file
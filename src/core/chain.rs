@@ -1,50 +1,87 @@
 // src/core/chain.rs
 
-use crate::core::block::Block;
-use crate::core::consensus::validator;
-use crate::core::state::State;
+use crate::core::block::{Block, Header};
+use crate::core::consensus::validator_set::ValidatorSet;
+use crate::core::consensus::{edfm, fees, forks, validator};
+use crate::core::execution;
+use crate::core::state::{Account, BlockContext, State, ValidatorInfo};
 use crate::core::genesis;
-use crate::storage::db::Storage;
+use crate::core::receipt::Receipt;
+use crate::core::transaction::Transaction;
+use crate::crypto::keys::KeyPair;
+use crate::storage::db::{PruningConfig, Storage, StorageMode, DB_PATH};
+
+/// Number of most recent blocks whose resulting state is kept in storage by full nodes.
+/// A reorg only undoes blocks after our last committed one. It rolls back to a snapshot
+/// within this depth, or replays from the closest older snapshot or genesis.
+const STATE_SNAPSHOT_DEPTH: usize = 128;
 
 #[derive(Debug)]
 pub struct Blockchain {
+    /// Most recent blocks, oldest first. This is the whole chain from genesis
+    /// unless the node runs in `StorageMode::Pruned`.
     pub blocks: Vec<Block>,
     storage: Storage,
+    storage_mode: StorageMode,
+    pruning: PruningConfig,
     pub state: State,
 }
 
 impl Blockchain {
-    pub fn new() -> Self {
+    pub fn new(storage_mode: StorageMode, pruning: PruningConfig) -> Self {
         let storage = Storage::new();
+        println!("💾 Storage mode: {:?}", storage_mode);
+        // A pruned node only keeps (and loads) this many blocks in full
+        let window = match storage_mode {
+            StorageMode::Pruned => pruning.keep_recent.max(1),
+            StorageMode::Full | StorageMode::Archive => usize::MAX,
+        };
 
         if let Some(tip_hash) = storage.get_tip_hash() {
             println!("Found existing blockchain. Loading from disk...");
             
-            // Load all blocks from storage to reconstruct the chain
+            // Load blocks from storage to reconstruct the chain, back to genesis or
+            // as far as our pruning window reaches
             let mut blocks = Vec::new();
             let mut current_hash = tip_hash.clone();
             
             while let Some(block) = storage.read_block(&current_hash) {
+                // Databases written before the height index existed get it filled in here
+                storage.write_height_index(block.header.block_number, &current_hash);
                 blocks.push(block.clone());
-                if block.header.block_number == 0 {
-                    break; // Reached genesis block
+                if block.header.block_number == 0 || blocks.len() == window {
+                    break; // Reached genesis block or the end of the window
                 }
                 current_hash = block.header.prev_block_hash.clone();
             }
             
             // Reverse to get blocks in correct order (genesis first)
             blocks.reverse();
-            let state = storage.read_state().unwrap_or_else(State::new);
+
+            // A database from another network would otherwise fail later with confusing errors
+            if let Some(stored) = storage.get_block_hash(0)
+                && stored != genesis::get_genesis_hash()
+            {
+                panic!(
+                    "❌ CRITICAL: Database {} was created for genesis block {} but {} defines genesis block {}",
+                    DB_PATH,
+                    stored,
+                    genesis::GENESIS_PATH,
+                    genesis::get_genesis_hash()
+                );
+            }
             
             println!("Loaded blockchain with {} blocks from storage", blocks.len());
             
-            Self { blocks, storage, state }
+            let mut chain = Self { blocks, storage, storage_mode, pruning, state: State::new() };
+            chain.state = chain.state_after(chain.blocks.len() - 1);
+            chain
         } else {
             println!("No existing blockchain found. Creating Genesis Block...");
             
             // Use shared genesis block to ensure network consistency
             let genesis_block = genesis::get_genesis_block();
-            let state = State::new();
+            let state = genesis::get_genesis_state();
             
             // Critical: Verify we're creating the correct genesis block
             if !genesis::is_valid_genesis_block(&genesis_block) {
@@ -52,17 +89,20 @@ impl Blockchain {
             }
             
             storage.write_block(&genesis_block);
-            storage.write_state(&state);
+            storage.write_state(&genesis_block.calculate_hash(), &state);
             
             Self {
                 blocks: vec![genesis_block],
                 storage,
+                storage_mode,
+                pruning,
                 state,
             }
         }
     }
 
-    /// Adds a new block to the blockchain after validation
+    /// Adds a new block to the blockchain after validation. Only committed blocks, carrying
+    /// a commit certificate from our validator set, are accepted.
     pub fn add_block(&mut self, block: Block) -> bool {
         let last_block = self.blocks.last().expect("Blockchain is empty!");
         
@@ -79,39 +119,306 @@ impl Blockchain {
         println!("🔍 Block validation - Last block hash: {}, New block prev_hash: {}", 
                  &last_block_hash[..8], &block.header.prev_block_hash[..8]);
         
-        if !validator::validate_block(&block, last_block) {
+        if !validator::validate_header(&block, last_block)
+            || !validator::validate_proposer(&block, &self.state)
+            || !validator::validate_commit(&block, &self.state)
+        {
             eprintln!("Validation Error: Block #{} failed validation", block.header.block_number);
             return false;
         }
         
-        // Persist block to storage and update chain
+        // Run the state transition; it also checks the block's receipts and state roots
+        let receipts = match execution::apply_block_with_receipts(&self.state, &block) {
+            Ok((next_state, receipts)) => {
+                self.state = next_state;
+                receipts
+            }
+            Err(e) => {
+                eprintln!("Validation Error: Block #{} failed to apply: {}", block.header.block_number, e);
+                return false;
+            }
+        };
+        
+        // Persist block, receipts and resulting state to storage and update chain
         self.storage.write_block(&block);
+        self.storage.write_receipts(&receipts);
+        self.storage.write_state(&block.calculate_hash(), &self.state);
         self.blocks.push(block);
-        println!("✅ Block #{} successfully validated and added to the chain.", self.blocks.last().unwrap().header.block_number);
+        self.prune_state_snapshots();
+        self.prune_window();
+        let block_number = self.height();
+        println!("✅ Block #{} successfully validated and added to the chain.", block_number);
+        for upgrade in forks::activated_at(block_number) {
+            println!("🍴 Network upgrade {:?} is active from block #{}", upgrade, block_number);
+        }
         true
     }
 
-    /// Persists the current blockchain state to disk
+    /// Number of the block at our tip
+    pub fn height(&self) -> u64 {
+        self.blocks.last().expect("Blockchain is empty!").header.block_number
+    }
+
+    /// Parameters the next block on top of our tip will be applied under if it is produced in `round`
+    pub fn next_block_context(&self, round: u32) -> BlockContext {
+        let last_block = self.blocks.last().expect("Blockchain is empty!");
+        let seed = edfm::round_seed(&last_block.calculate_hash(), round);
+        BlockContext {
+            chain_id: genesis::chain_id(),
+            block_number: last_block.header.block_number + 1,
+            base_fee: fees::next_base_fee(&last_block.header),
+            proposer: edfm::select_proposer(&seed, self.state.validators()),
+            rules: forks::rules_at(last_block.header.block_number + 1),
+        }
+    }
+
+    /// Builds and signs the next block on top of our tip as the proposer of `round`, executing
+    /// the transactions against a copy of the state to fill in the receipts and state roots
+    pub fn create_block(&self, transactions: Vec<Transaction>, round: u32, keypair: &KeyPair) -> Block {
+        let last_block = self.blocks.last().expect("Blockchain is empty!");
+        let mut block = Block::new(
+            last_block.header.block_number + 1,
+            last_block.calculate_hash(),
+            fees::next_base_fee(&last_block.header),
+            transactions,
+        );
+        block.header.proposer = keypair.get_address();
+        block.header.round = round;
+        
+        let (next_state, receipts) = execution::execute_block(&self.state, &block)
+            .expect("Selected transactions failed to apply");
+        block.header.receipts_root = Receipt::compute_receipts_root(&receipts);
+        block.header.state_root = next_state.state_root();
+        block.sign(keypair);
+        block
+    }
+
+    /// Looks up the outcome of a transaction included in the chain
+    pub fn get_receipt(&self, tx_hash: &str) -> Option<Receipt> {
+        self.storage.read_receipt(tx_hash)
+    }
+
+    /// Block at `block_number` if it is within `blocks`
+    pub fn block_at(&self, block_number: u64) -> Option<&Block> {
+        let first_block_number = self.blocks.first()?.header.block_number;
+        let index = usize::try_from(block_number.checked_sub(first_block_number)?).ok()?;
+        self.blocks.get(index)
+    }
+
+    /// Header of the canonical block at `block_number`, including blocks pruned down to their header
+    pub fn header_at(&self, block_number: u64) -> Option<Header> {
+        if let Some(block) = self.block_at(block_number) {
+            return Some(block.header.clone());
+        }
+        self.storage.read_header(&self.storage.get_block_hash(block_number)?)
+    }
+
+    /// State as it was after the block at `block_number`. Always available on archive nodes;
+    /// full nodes only keep the last `STATE_SNAPSHOT_DEPTH` blocks, pruned nodes their
+    /// window and checkpoints.
+    pub fn state_at(&self, block_number: u64) -> Option<State> {
+        let block_hash = self.storage.get_block_hash(block_number)?;
+        let header = self.storage.read_header(&block_hash)?;
+        self.storage
+            .read_state(&block_hash)
+            .filter(|state| state.state_root() == header.state_root)
+    }
+
+    /// Account as it was after the block at `block_number`
+    pub fn account_at(&self, address: &str, block_number: u64) -> Option<Account> {
+        self.state_at(block_number).map(|state| state.get_account(address))
+    }
+
+    /// Validator entry as it was after the block at `block_number`; `None` if the height
+    /// is not available or the address was not a validator then
+    pub fn validator_at(&self, address: &str, block_number: u64) -> Option<ValidatorInfo> {
+        self.state_at(block_number)?.validators().get(address).cloned()
+    }
+
+    /// Validator set as it was after the block at `block_number`
+    pub fn validators_at(&self, block_number: u64) -> Option<ValidatorSet> {
+        self.state_at(block_number).map(|state| state.validators().clone())
+    }
+
+    /// Persists the current blockchain state to disk, as the state after our tip
     pub fn save_state(&self) {
-        self.storage.write_state(&self.state);
+        let tip_hash = self.blocks.last().expect("Blockchain is empty!").calculate_hash();
+        self.storage.write_state(&tip_hash, &self.state);
+    }
+
+    /// Handle to the chain's storage, for work that runs without holding the chain
+    pub fn storage(&self) -> Storage {
+        self.storage.clone()
+    }
+
+    /// State after the block at `index` in `blocks`: its stored snapshot if it is still there
+    /// and matches the block's state root, otherwise replayed from the closest earlier
+    /// snapshot in `blocks`, or from genesis
+    fn state_after(&self, index: usize) -> State {
+        let snapshot = |i: usize| {
+            let block = &self.blocks[i];
+            self.storage
+                .read_state(&block.calculate_hash())
+                .filter(|state| state.state_root() == block.header.state_root)
+        };
+        if let Some(state) = snapshot(index) {
+            return state;
+        }
+        
+        let (start, base_state) = match (0..index).rev().find_map(|i| snapshot(i).map(|state| (i, state))) {
+            Some(found) => found,
+            None if self.blocks[0].header.block_number == 0 => (0, genesis::get_genesis_state()),
+            None => panic!("❌ CRITICAL: No state snapshot left to rebuild the state of block #{}",
+                           self.blocks[index].header.block_number),
+        };
+        println!("🔄 Rebuilding state by replaying {} blocks from block #{}...",
+                 index - start, self.blocks[start].header.block_number);
+        execution::replay_blocks(&base_state, &self.blocks[start..=index])
+            .unwrap_or_else(|e| panic!("❌ CRITICAL: Stored chain failed to replay: {}", e))
+    }
+
+    /// Drops the snapshot that just fell out of the `STATE_SNAPSHOT_DEPTH` window.
+    /// Archive nodes keep them all, pruned nodes drop them with the block in `prune_window`.
+    fn prune_state_snapshots(&self) {
+        if self.storage_mode == StorageMode::Full && self.blocks.len() > STATE_SNAPSHOT_DEPTH {
+            let expired = &self.blocks[self.blocks.len() - 1 - STATE_SNAPSHOT_DEPTH];
+            self.storage.delete_state(&expired.calculate_hash());
+        }
+    }
+
+    /// On pruned nodes, moves blocks beyond the most recent `keep_recent` out of memory and
+    /// reduces them to their header in storage, unless they are checkpoints
+    fn prune_window(&mut self) {
+        if self.storage_mode != StorageMode::Pruned || self.blocks.len() <= self.pruning.keep_recent.max(1) {
+            return;
+        }
+        
+        let expired = self.blocks.len() - self.pruning.keep_recent.max(1);
+        let checkpoint_interval = self.pruning.checkpoint_interval.max(1);
+        for block in self.blocks.drain(..expired) {
+            if block.header.block_number % checkpoint_interval != 0 {
+                self.storage.prune_block(&block);
+            }
+        }
     }
 
-    /// Simple chain replacement for synchronization - minimal validation
+    /// Index in `blocks` of the last block that is final: the last one carrying a commit
+    /// certificate, or the genesis block. Blocks up to it are never rolled back.
+    fn last_committed(&self) -> usize {
+        self.blocks
+            .iter()
+            .rposition(|block| block.commit.is_some() || block.header.block_number == 0)
+            .unwrap_or(0)
+    }
+
+    /// Simple chain replacement for synchronization. State is rolled back to the last block
+    /// both chains share and the new branch is validated, commit certificates included, and
+    /// applied from there before it replaces ours. Committed blocks are final: the shared
+    /// block may not be before our last committed one.
+    /// Either chain may start after genesis if its node prunes.
     pub fn replace_chain_simple(&mut self, new_blocks: Vec<Block>) -> bool {
         // Only replace if new chain is longer
-        if new_blocks.is_empty() || new_blocks.len() <= self.blocks.len() {
+        let Some(new_tip) = new_blocks.last() else {
+            return false;
+        };
+        if new_tip.header.block_number <= self.height() {
+            return false;
+        }
+        if !new_blocks.windows(2).all(|pair| pair[1].header.block_number == pair[0].header.block_number + 1) {
+            eprintln!("❌ Peer chain block numbers are not contiguous - cannot replace chain");
             return false;
         }
+        
+        // The first block both chains hold must be the same one
+        let our_first = self.blocks[0].header.block_number;
+        let their_first = new_blocks[0].header.block_number;
+        let first_shared = our_first.max(their_first);
+        if first_shared > self.height() {
+            eprintln!("❌ Peer chain starts at block #{}, beyond our tip - cannot replace chain", their_first);
+            return false;
+        }
+        let ours = (first_shared - our_first) as usize;
+        let theirs = (first_shared - their_first) as usize;
+        let Some(their_shared) = new_blocks.get(theirs) else {
+            return false;
+        };
+        if self.blocks[ours].calculate_hash() != their_shared.calculate_hash() {
+            if first_shared == 0 {
+                eprintln!("❌ Genesis blocks don't match - cannot replace chain");
+            } else {
+                eprintln!("❌ Peer chain differs from ours at block #{} - cannot replace chain", first_shared);
+            }
+            return false;
+        }
+
+        // Find the common ancestor, as an index into our blocks and into theirs
+        let shared = self.blocks[ours..]
+            .iter()
+            .zip(&new_blocks[theirs..])
+            .take_while(|(our_block, their_block)| our_block.calculate_hash() == their_block.calculate_hash())
+            .count() - 1;
+        let (ancestor, their_ancestor) = (ours + shared, theirs + shared);
+        let last_committed = self.last_committed();
+        if ancestor < last_committed {
+            eprintln!("❌ Peer chain forks from ours at block #{}, before our last committed block #{} - refusing to roll back committed blocks",
+                      self.blocks[ancestor].header.block_number, self.blocks[last_committed].header.block_number);
+            return false;
+        }
+        println!("🔀 Common ancestor is block #{}, rolling back {} blocks", 
+                 self.blocks[ancestor].header.block_number, self.blocks.len() - 1 - ancestor);
+
+        let mut state = self.state_after(ancestor);
+        let mut snapshots = Vec::new();
+        let mut receipts = Vec::new();
+        for pair in new_blocks[their_ancestor..].windows(2) {
+            let (previous_block, block) = (&pair[0], &pair[1]);
+            if !validator::validate_header(block, previous_block)
+                || !validator::validate_proposer(block, &state)
+                || !validator::validate_commit(block, &state)
+            {
+                eprintln!("❌ Block #{} of the new chain is invalid", block.header.block_number);
+                return false;
+            }
+            match execution::apply_block_with_receipts(&state, block) {
+                Ok((next_state, block_receipts)) => {
+                    snapshots.push((block.calculate_hash(), next_state.clone()));
+                    state = next_state;
+                    receipts.extend(block_receipts);
+                }
+                Err(e) => {
+                    eprintln!("❌ Block #{} of the new chain failed to apply: {}", block.header.block_number, e);
+                    return false;
+                }
+            }
+        }
 
-        println!("🔄 Replacing chain: {} blocks -> {} blocks", 
-                 self.blocks.len(), new_blocks.len());
+        println!("🔄 Replacing chain: tip #{} -> tip #{}", 
+                 self.height(), new_tip.header.block_number);
+        
+        // Abandoned blocks lose their state snapshots and receipts; their bodies are left
+        // for the garbage collector
+        for block in &self.blocks[ancestor + 1..] {
+            self.storage.delete_state(&block.calculate_hash());
+            self.storage.delete_receipts(block);
+        }
         
-        // Replace the entire chain
-        self.blocks = new_blocks;
-        if let Some(last_block) = self.blocks.last() {
-            self.storage.write_block(last_block);
+        // Replace the chain and the state derived from it
+        for block in &new_blocks[their_ancestor + 1..] {
+            self.storage.write_block(block);
         }
-        self.save_state();
+        for (block_hash, snapshot) in &snapshots {
+            self.storage.write_state(block_hash, snapshot);
+        }
+        self.storage.write_receipts(&receipts);
+        self.blocks.truncate(ancestor + 1);
+        self.blocks.extend(new_blocks.into_iter().skip(their_ancestor + 1));
+        self.state = state;
+        if self.storage_mode == StorageMode::Full {
+            for index in ancestor + 1..self.blocks.len().saturating_sub(STATE_SNAPSHOT_DEPTH) {
+                self.storage.delete_state(&self.blocks[index].calculate_hash());
+            }
+        }
+        self.prune_window();
         true
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,21 @@
 // src/core/consensus/edfm.rs
 
+use crate::core::consensus::validator_set::ValidatorSet;
 use crate::crypto::hash::calculate_hash;
-use std::collections::HashMap;
+
+/// Seed proposer selection uses in `round` of the height following `prev_block_hash`.
+/// Round 0 uses the previous block hash itself; later rounds rotate to other proposers.
+pub fn round_seed(prev_block_hash: &str, round: u32) -> String {
+    if round == 0 {
+        return prev_block_hash.to_string();
+    }
+    calculate_hash(&(prev_block_hash, round))
+}
 
 /// EDFM (Ethereum-inspired Dynamic Federated Model) consensus
 /// Selects a proposer based on stake and random seed
-pub fn select_proposer(seed: &str, validators: &HashMap<String, crate::core::state::ValidatorInfo>) -> Option<String> {
+/// Validators are walked in the set's canonical order, so every node picks the same one
+pub fn select_proposer(seed: &str, validators: &ValidatorSet) -> Option<String> {
     if validators.is_empty() {
         return None;
     }
@@ -20,7 +30,7 @@ pub fn select_proposer(seed: &str, validators: &HashMap<String, crate::core::sta
     let hash_num = u64::from_str_radix(&hash[..16], 16).unwrap_or(0);
     
     // Calculate total stake
-    let total_stake: u64 = validators.values().map(|v| v.stake).sum();
+    let total_stake = validators.total_power();
     
     if total_stake == 0 {
         return None;
@@ -30,15 +40,15 @@ pub fn select_proposer(seed: &str, validators: &HashMap<String, crate::core::sta
     let mut cumulative_stake = 0u64;
     let target = hash_num % total_stake;
     
-    for (address, validator) in validators {
+    for validator in validators.iter() {
         cumulative_stake += validator.stake;
         if cumulative_stake > target {
             println!("🎲 EDFM selected proposer: {} (stake: {}, target: {})", 
-                     address, validator.stake, target);
-            return Some(address.clone());
+                     validator.address, validator.stake, target);
+            return Some(validator.address.clone());
         }
     }
     
     // Fallback: return first validator
-    validators.keys().next().cloned()
+    validators.by_index(0).map(|validator| validator.address.clone())
 }
\ No newline at end of file
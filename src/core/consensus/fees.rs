@@ -0,0 +1,42 @@
+// src/core/consensus/fees.rs
+
+use crate::core::block::Header;
+use crate::core::consensus::{forks, limits};
+
+/// Default base fee per unit of gas of the genesis block, see `genesis::ConsensusParams`
+pub const INITIAL_BASE_FEE: u64 = 10;
+
+/// The base fee never drops below this, so it can always rise again.
+/// Initial value of `forks::Rules::min_base_fee`.
+pub const MIN_BASE_FEE: u64 = 1;
+
+/// Limits how fast the base fee moves: at most 1/8 per block.
+/// Initial value of `forks::Rules::base_fee_change_denominator`.
+pub const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Computes the base fee a block must carry from its parent.
+/// Parents using more gas than the target raise it, emptier ones lower it,
+/// proportionally to the distance, as far as the rules of the new block allow.
+pub fn next_base_fee(parent: &Header) -> u64 {
+    let rules = forks::rules_at(parent.block_number + 1);
+    let parent_base_fee = parent.base_fee;
+    let used = parent.gas_used;
+    let target = limits::gas_target(parent.gas_limit).max(1);
+
+    if used > target {
+        let delta = parent_base_fee.saturating_mul(used - target) / target / rules.base_fee_change_denominator;
+        parent_base_fee.saturating_add(delta.max(1))
+    } else {
+        let delta = parent_base_fee.saturating_mul(target - used) / target / rules.base_fee_change_denominator;
+        parent_base_fee.saturating_sub(delta).max(rules.min_base_fee)
+    }
+}
+
+/// Splits what a transaction pays per unit of gas at the given base fee into (burned, tip).
+/// Returns None if the transaction's fee cap does not cover the base fee.
+pub fn fee_split(max_fee_per_gas: u64, priority_fee_per_gas: u64, base_fee: u64) -> Option<(u64, u64)> {
+    if max_fee_per_gas < base_fee {
+        return None;
+    }
+    Some((base_fee, priority_fee_per_gas.min(max_fee_per_gas - base_fee)))
+}
@@ -0,0 +1,89 @@
+// src/core/consensus/forks.rs
+
+use crate::core::consensus::{fees, limits};
+use crate::core::genesis;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Named protocol upgrades. A network activates them at the heights listed in the
+/// `forks` of its genesis spec; upgrades it does not list never activate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Upgrade {
+    /// Doubles the block and transaction size limits
+    Thulium,
+}
+
+/// Activation height of every upgrade a network schedules
+pub type ForkSchedule = BTreeMap<Upgrade, u64>;
+
+/// Consensus rules in force at a given height. Every rule that may change in an
+/// upgrade lives here instead of in a constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub max_block_size: usize,
+    pub max_transaction_size: usize,
+    /// How far ahead of our clock a block's timestamp may be, in seconds
+    pub max_future_block_time: u64,
+    pub min_base_fee: u64,
+    pub base_fee_change_denominator: u64,
+}
+
+impl Rules {
+    /// Rules of a network before any upgrade
+    pub fn initial() -> Self {
+        Self {
+            max_block_size: limits::MAX_BLOCK_SIZE,
+            max_transaction_size: limits::MAX_TRANSACTION_SIZE,
+            max_future_block_time: 10,
+            min_base_fee: fees::MIN_BASE_FEE,
+            base_fee_change_denominator: fees::BASE_FEE_CHANGE_DENOMINATOR,
+        }
+    }
+
+    fn apply(&mut self, upgrade: Upgrade) {
+        match upgrade {
+            Upgrade::Thulium => {
+                self.max_block_size *= 2;
+                self.max_transaction_size *= 2;
+            }
+        }
+    }
+
+    /// Rules at `block_number` under `schedule`: the initial rules with every upgrade
+    /// activated by then applied in activation order
+    pub fn for_schedule(schedule: &ForkSchedule, block_number: u64) -> Self {
+        let mut active: Vec<(u64, Upgrade)> = schedule
+            .iter()
+            .filter(|(_, height)| **height <= block_number)
+            .map(|(upgrade, height)| (*height, *upgrade))
+            .collect();
+        active.sort();
+
+        let mut rules = Self::initial();
+        for (_, upgrade) in active {
+            rules.apply(upgrade);
+        }
+        rules
+    }
+}
+
+/// Rules in force at `block_number` on this node's network
+pub fn rules_at(block_number: u64) -> Rules {
+    Rules::for_schedule(&genesis::spec().forks, block_number)
+}
+
+/// Whether `upgrade` is active at `block_number` on this node's network
+pub fn is_active(upgrade: Upgrade, block_number: u64) -> bool {
+    genesis::spec().forks.get(&upgrade).is_some_and(|height| *height <= block_number)
+}
+
+/// Upgrades that take effect exactly at `block_number`
+pub fn activated_at(block_number: u64) -> Vec<Upgrade> {
+    genesis::spec()
+        .forks
+        .iter()
+        .filter(|(_, height)| **height == block_number)
+        .map(|(upgrade, _)| *upgrade)
+        .collect()
+}
@@ -0,0 +1,42 @@
+// src/core/consensus/limits.rs
+
+/// Default maximum total gas of the transactions in one block; a network sets its own
+/// in the genesis spec, see `genesis::block_gas_limit`
+pub const BLOCK_GAS_LIMIT: u64 = 2_100_000;
+
+/// Maximum size of a block serialized as JSON, which is how it travels between peers.
+/// Initial value of `forks::Rules::max_block_size`.
+pub const MAX_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// Room left in `MAX_BLOCK_SIZE` for the header and JSON framing
+pub const HEADER_SIZE_ALLOWANCE: usize = 4 * 1024;
+
+/// Maximum size of a single serialized transaction.
+/// Initial value of `forks::Rules::max_transaction_size`.
+pub const MAX_TRANSACTION_SIZE: usize = 128 * 1024;
+
+/// Gas every transaction pays regardless of its kind
+pub const TX_BASE_GAS: u64 = 21_000;
+
+/// Extra gas for transactions that touch the validator set
+pub const STAKING_GAS: u64 = 20_000;
+
+/// Extra gas for creating a contract
+pub const DEPLOY_GAS: u64 = 32_000;
+
+/// Extra gas for creating a multisig account
+pub const ACCOUNT_CREATION_GAS: u64 = 25_000;
+
+/// Gas per owner signature verified or registered on a multisig account
+pub const SIGNATURE_GAS: u64 = 3_000;
+
+/// Gas per call inside a batch transaction
+pub const BATCH_CALL_GAS: u64 = 9_000;
+
+/// Gas per byte of contract code or call data
+pub const DATA_BYTE_GAS: u64 = 16;
+
+/// Gas target per block, half the limit: the base fee rises above it and falls below it
+pub fn gas_target(gas_limit: u64) -> u64 {
+    gas_limit / 2
+}
@@ -1,7 +1,14 @@
 // src/core/consensus/mod.rs
 
 pub mod validator;
+pub mod validator_set;
 pub mod edfm;
 pub mod block_time;
+pub mod fees;
+pub mod forks;
+pub mod limits;
+pub mod staking;
+pub mod tendermint;
+pub mod vote;
 
-pub use block_time::BlockTimeManager;
\ No newline at end of file
+pub use block_time::BlockTimeManager;
@@ -0,0 +1,22 @@
+// src/core/consensus/staking.rs
+
+use serde::{Deserialize, Serialize};
+
+/// Number of blocks between a staking transaction and the block from which
+/// the validator set reflects it
+pub const VALIDATOR_UPDATE_DELAY: u64 = 10;
+
+/// A validator set change made by a staking transaction that has not taken effect yet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StakeChange {
+    /// Adds stake that is already locked from the account's balance
+    Bond { address: String, amount: u64 },
+    /// Removes stake and returns it to the account's balance
+    Unbond { address: String, amount: u64 },
+}
+
+/// First block whose proposer is chosen from a validator set that includes
+/// a staking transaction applied in `block_number`
+pub fn effective_height(block_number: u64) -> u64 {
+    block_number.saturating_add(VALIDATOR_UPDATE_DELAY)
+}
@@ -0,0 +1,685 @@
+// src/core/consensus/tendermint.rs
+
+use crate::core::block::Block;
+use crate::core::consensus::edfm;
+use crate::core::consensus::validator_set::ValidatorSet;
+use crate::core::consensus::vote::{CommitCertificate, Vote, VoteSet, VoteType};
+use crate::core::error::Error;
+use crate::core::genesis;
+use crate::crypto::hash::calculate_hash;
+use crate::crypto::keys::KeyPair;
+use crate::crypto::signature;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long to wait for the proposal of round 0; every later round waits longer
+const TIMEOUT_PROPOSE: Duration = Duration::from_millis(3000);
+const TIMEOUT_PROPOSE_DELTA: Duration = Duration::from_millis(1000);
+/// How long to wait for a decisive prevote or precommit quorum once votes from
+/// more than two thirds of the stake arrived without one
+const TIMEOUT_VOTE: Duration = Duration::from_millis(1000);
+const TIMEOUT_VOTE_DELTA: Duration = Duration::from_millis(500);
+/// Longest any timeout gets, however many rounds have passed
+const MAX_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Step of a consensus round, in the order a round goes through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Step {
+    Propose,
+    PreVote,
+    PreCommit,
+}
+
+/// A block proposed for a height and round, signed by the proposer of that round.
+/// `valid_round` is set when the block is re-proposed after it got a prevote quorum
+/// in that earlier round.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    pub height: u64,
+    pub round: u32,
+    pub valid_round: Option<u32>,
+    pub block: Block,
+    pub signature: String,
+}
+
+impl Proposal {
+    pub fn new(height: u64, round: u32, valid_round: Option<u32>, block: Block, keypair: &KeyPair) -> Self {
+        let mut proposal = Self {
+            height,
+            round,
+            valid_round,
+            block,
+            signature: String::new(),
+        };
+        proposal.signature = signature::sign_hash(&proposal.signing_hash(), keypair);
+        proposal
+    }
+
+    /// Digest the proposer signs, covering the chain ID and the proposed block's hash
+    pub fn signing_hash(&self) -> String {
+        calculate_hash(&(
+            genesis::chain_id(),
+            self.height,
+            self.round,
+            self.valid_round,
+            self.block.calculate_hash(),
+        ))
+    }
+
+    /// Address of the validator that signed the proposal
+    pub fn proposer(&self) -> Option<String> {
+        signature::recover_address(&self.signing_hash(), &self.signature)
+    }
+}
+
+/// A timeout the engine asked for, handed back to `on_timeout` once it expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    pub height: u64,
+    pub round: u32,
+    pub step: Step,
+    pub duration: Duration,
+}
+
+/// What the node must do in reaction to an engine input
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// We propose this round and have no earlier valid block: build one and pass it to `propose`
+    BuildProposal { height: u64, round: u32 },
+    /// Broadcast our proposal
+    Proposal(Proposal),
+    /// Broadcast our vote
+    Vote(Vote),
+    /// Call `on_timeout` after `duration`
+    ScheduleTimeout(Timeout),
+    /// The block got a precommit quorum in `round` and is final. It carries those
+    /// precommits as its commit certificate.
+    Commit { block: Block, round: u32 },
+}
+
+/// Tendermint consensus for one height at a time.
+///
+/// Every height runs in rounds. In each round the proposer chosen by `edfm` for that
+/// round proposes a block, validators prevote for it or for nil, then precommit. A block
+/// is final once validators holding more than two thirds of the stake precommitted to it
+/// in the same round. A round that does not get there times out and the next round starts
+/// with another proposer.
+///
+/// A validator that precommitted to a block is locked on it: in later rounds it only
+/// prevotes for that block, unless another block got a prevote quorum in a round after
+/// the lock. Together with the quorum rules this keeps two different blocks from becoming
+/// final at the same height.
+///
+/// The engine does no I/O. The node feeds it proposals, votes and expired timeouts and
+/// carries out the `Output`s it returns.
+pub struct Tendermint {
+    keypair: Arc<KeyPair>,
+    height: u64,
+    round: u32,
+    step: Step,
+    prev_block_hash: String,
+    validators: ValidatorSet,
+    /// Round and block we are locked on
+    locked: Option<(u32, Block)>,
+    /// Latest round and block that got a prevote quorum, re-proposed when we propose again
+    valid: Option<(u32, Block)>,
+    /// First proposal from the right proposer for each round
+    proposals: HashMap<u32, Proposal>,
+    /// Whether each proposed block, by hash, is a valid successor of our tip
+    proposal_validity: HashMap<String, bool>,
+    pre_votes: VoteSet,
+    pre_commits: VoteSet,
+    /// Rules below fire only once per round
+    pre_vote_timeout_scheduled: bool,
+    pre_commit_timeout_scheduled: bool,
+    valid_block_updated: bool,
+    decided: bool,
+}
+
+impl Tendermint {
+    /// Creates an engine signing with `keypair`. It is idle until `start_height`.
+    pub fn new(keypair: Arc<KeyPair>) -> Self {
+        Self {
+            keypair,
+            height: 0,
+            round: 0,
+            step: Step::Propose,
+            prev_block_hash: String::new(),
+            validators: ValidatorSet::new(),
+            locked: None,
+            valid: None,
+            proposals: HashMap::new(),
+            proposal_validity: HashMap::new(),
+            pre_votes: VoteSet::new(VoteType::PreVote),
+            pre_commits: VoteSet::new(VoteType::PreCommit),
+            pre_vote_timeout_scheduled: false,
+            pre_commit_timeout_scheduled: false,
+            valid_block_updated: false,
+            decided: false,
+        }
+    }
+
+    /// Height being decided, 0 before the first `start_height`
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// Validator set deciding the current height
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Whether a block was committed at the current height
+    pub fn is_decided(&self) -> bool {
+        self.decided
+    }
+
+    /// Validator expected to propose in `round` of the current height
+    pub fn proposer(&self, round: u32) -> Option<String> {
+        edfm::select_proposer(&edfm::round_seed(&self.prev_block_hash, round), &self.validators)
+    }
+
+    /// Starts deciding block `height`, on top of `prev_block_hash` and with the validator
+    /// set of our tip. Everything about the previous height is dropped.
+    pub fn start_height(&mut self, height: u64, prev_block_hash: String, validators: ValidatorSet) -> Vec<Output> {
+        self.height = height;
+        self.prev_block_hash = prev_block_hash;
+        self.validators = validators;
+        self.locked = None;
+        self.valid = None;
+        self.proposals.clear();
+        self.proposal_validity.clear();
+        self.pre_votes = VoteSet::new(VoteType::PreVote);
+        self.pre_commits = VoteSet::new(VoteType::PreCommit);
+        self.decided = false;
+
+        let mut outputs = Vec::new();
+        self.start_round(0, &mut outputs);
+        self.process(&mut outputs);
+        outputs
+    }
+
+    /// Proposes `block` in the current round, after `BuildProposal` asked for it
+    pub fn propose(&mut self, block: Block) -> Vec<Output> {
+        let mut outputs = Vec::new();
+        if block.header.block_number != self.height || self.step != Step::Propose || self.decided {
+            return outputs;
+        }
+        self.broadcast_proposal(None, block, &mut outputs);
+        self.process(&mut outputs);
+        outputs
+    }
+
+    /// Handles a proposal from a peer. `valid` tells whether its block is a valid
+    /// successor of our tip; an invalid block is prevoted nil.
+    pub fn on_proposal(&mut self, proposal: Proposal, valid: bool) -> Result<Vec<Output>, Error> {
+        let mut outputs = Vec::new();
+        if proposal.height != self.height || self.decided {
+            return Ok(outputs);
+        }
+        let invalid = |reason: &str| Error::InvalidProposal {
+            height: proposal.height,
+            round: proposal.round,
+            reason: reason.to_string(),
+        };
+        if proposal.block.header.block_number != proposal.height {
+            return Err(invalid("block number does not match the height"));
+        }
+        if proposal.valid_round.is_some_and(|valid_round| valid_round >= proposal.round) {
+            return Err(invalid("valid round is not before the proposal round"));
+        }
+        // The block's round picks its proposer, see `validator::validate_proposer`. It is the
+        // round the block was first proposed in: this one, or for a re-proposal at the latest
+        // the round it got its prevote quorum in.
+        let block_round = proposal.block.header.round;
+        let round_matches = match proposal.valid_round {
+            None => block_round == proposal.round,
+            Some(valid_round) => block_round <= valid_round,
+        };
+        if !round_matches {
+            return Err(invalid("block round does not match the proposal"));
+        }
+        if proposal.proposer() != self.proposer(proposal.round) {
+            return Err(invalid("not signed by the proposer of this round"));
+        }
+        if self.proposals.contains_key(&proposal.round) {
+            return Ok(outputs);
+        }
+
+        self.proposal_validity.insert(proposal.block.calculate_hash(), valid);
+        self.proposals.insert(proposal.round, proposal);
+        self.process(&mut outputs);
+        Ok(outputs)
+    }
+
+    /// Handles a prevote or precommit from a peer
+    pub fn on_vote(&mut self, vote: Vote) -> Result<Vec<Output>, Error> {
+        let mut outputs = Vec::new();
+        if vote.height != self.height || self.decided {
+            return Ok(outputs);
+        }
+        let added = match vote.vote_type {
+            VoteType::PreVote => self.pre_votes.add(vote, &self.validators)?,
+            VoteType::PreCommit => self.pre_commits.add(vote, &self.validators)?,
+        };
+        if added {
+            self.process(&mut outputs);
+        }
+        Ok(outputs)
+    }
+
+    /// Handles a timeout scheduled earlier. Timeouts of past rounds or steps are ignored.
+    pub fn on_timeout(&mut self, timeout: Timeout) -> Vec<Output> {
+        let mut outputs = Vec::new();
+        if timeout.height != self.height || timeout.round != self.round || self.decided {
+            return outputs;
+        }
+        match timeout.step {
+            Step::Propose if self.step == Step::Propose => {
+                println!("⏰ No proposal for #{} in round {}, prevoting nil", self.height, self.round);
+                self.cast_vote(VoteType::PreVote, None, &mut outputs);
+                self.step = Step::PreVote;
+            }
+            Step::PreVote if self.step == Step::PreVote => {
+                self.cast_vote(VoteType::PreCommit, None, &mut outputs);
+                self.step = Step::PreCommit;
+            }
+            Step::PreCommit => {
+                let Some(next_round) = self.round.checked_add(1) else {
+                    return outputs;
+                };
+                println!("⏰ Round {} of #{} timed out, moving to round {}", self.round, self.height, next_round);
+                self.start_round(next_round, &mut outputs);
+            }
+            _ => return outputs,
+        }
+        self.process(&mut outputs);
+        outputs
+    }
+
+    fn start_round(&mut self, round: u32, outputs: &mut Vec<Output>) {
+        self.round = round;
+        self.step = Step::Propose;
+        self.pre_vote_timeout_scheduled = false;
+        self.pre_commit_timeout_scheduled = false;
+        self.valid_block_updated = false;
+
+        if self.proposer(round).as_deref() == Some(&self.keypair.get_address()) {
+            println!("🎯 We propose block #{} in round {}", self.height, round);
+            match self.valid.clone() {
+                Some((valid_round, block)) => self.broadcast_proposal(Some(valid_round), block, outputs),
+                None => outputs.push(Output::BuildProposal { height: self.height, round }),
+            }
+        } else {
+            self.schedule_timeout(Step::Propose, outputs);
+        }
+    }
+
+    fn broadcast_proposal(&mut self, valid_round: Option<u32>, block: Block, outputs: &mut Vec<Output>) {
+        let proposal = Proposal::new(self.height, self.round, valid_round, block, &self.keypair);
+        self.proposal_validity.insert(proposal.block.calculate_hash(), true);
+        self.proposals.insert(self.round, proposal.clone());
+        outputs.push(Output::Proposal(proposal));
+    }
+
+    fn schedule_timeout(&self, step: Step, outputs: &mut Vec<Output>) {
+        let (base, delta) = match step {
+            Step::Propose => (TIMEOUT_PROPOSE, TIMEOUT_PROPOSE_DELTA),
+            Step::PreVote | Step::PreCommit => (TIMEOUT_VOTE, TIMEOUT_VOTE_DELTA),
+        };
+        outputs.push(Output::ScheduleTimeout(Timeout {
+            height: self.height,
+            round: self.round,
+            step,
+            duration: base.saturating_add(delta.saturating_mul(self.round)).min(MAX_TIMEOUT),
+        }));
+    }
+
+    // Signs and records our vote, if we are a validator
+    fn cast_vote(&mut self, vote_type: VoteType, block_hash: Option<String>, outputs: &mut Vec<Output>) {
+        if !self.validators.contains(&self.keypair.get_address()) {
+            return;
+        }
+        let vote = Vote::new(vote_type, self.height, self.round, block_hash, &self.keypair);
+        let votes = match vote_type {
+            VoteType::PreVote => &mut self.pre_votes,
+            VoteType::PreCommit => &mut self.pre_commits,
+        };
+        if votes.add(vote.clone(), &self.validators) == Ok(true) {
+            outputs.push(Output::Vote(vote));
+        }
+    }
+
+    // Proposal of `round` with its block hash and validity
+    fn proposal(&self, round: u32) -> Option<(Block, String, bool)> {
+        let proposal = self.proposals.get(&round)?;
+        let hash = proposal.block.calculate_hash();
+        let valid = self.proposal_validity.get(&hash).copied().unwrap_or(false);
+        Some((proposal.block.clone(), hash, valid))
+    }
+
+    // Applies the consensus rules until none of them fires anymore
+    fn process(&mut self, outputs: &mut Vec<Output>) {
+        while !self.decided && self.apply_rule(outputs) {}
+    }
+
+    // Applies the first rule whose conditions hold. Returns whether one did.
+    fn apply_rule(&mut self, outputs: &mut Vec<Output>) -> bool {
+        let (height, round) = (self.height, self.round);
+
+        // A precommit quorum for a valid proposed block of any round decides the height
+        let rounds: Vec<u32> = self.proposals.keys().copied().collect();
+        for proposal_round in rounds {
+            let (mut block, hash, valid) = self.proposal(proposal_round).unwrap();
+            if valid && self.pre_commits.has_quorum(height, proposal_round, Some(&hash), &self.validators) {
+                println!("🎉 Block #{} committed in round {}", height, proposal_round);
+                self.decided = true;
+                block.commit = Some(CommitCertificate {
+                    round: proposal_round,
+                    votes: self.pre_commits.votes_for(height, proposal_round, Some(&hash)),
+                });
+                outputs.push(Output::Commit { block, round: proposal_round });
+                return true;
+            }
+        }
+
+        // More than a third of the stake is in a later round: at least one honest
+        // validator is there, so catch up instead of waiting for our timeouts
+        if let Some(later_round) = self.skip_round() {
+            println!("⏩ Skipping from round {} to round {} of #{}", round, later_round, height);
+            self.start_round(later_round, outputs);
+            return true;
+        }
+
+        if self.step == Step::Propose
+            && let Some((_, hash, valid)) = self.proposal(round)
+        {
+            let valid_round = self.proposals[&round].valid_round;
+            let acceptable = match valid_round {
+                // A fresh block: prevote for it unless we are locked on another one
+                None => Some(self.locked.as_ref().is_none_or(|(_, locked)| locked.calculate_hash() == hash)),
+                // A re-proposed block: once its prevote quorum is visible, prevote for it
+                // unless we locked on another block after that quorum
+                Some(valid_round) if self.pre_votes.has_quorum(height, valid_round, Some(&hash), &self.validators) => Some(
+                    self.locked
+                        .as_ref()
+                        .is_none_or(|(locked_round, locked)| *locked_round <= valid_round || locked.calculate_hash() == hash),
+                ),
+                Some(_) => None,
+            };
+            if let Some(acceptable) = acceptable {
+                self.cast_vote(VoteType::PreVote, (valid && acceptable).then_some(hash), outputs);
+                self.step = Step::PreVote;
+                return true;
+            }
+        }
+
+        if self.step == Step::PreVote
+            && !self.pre_vote_timeout_scheduled
+            && self.pre_votes.has_any_quorum(height, round, &self.validators)
+        {
+            self.pre_vote_timeout_scheduled = true;
+            self.schedule_timeout(Step::PreVote, outputs);
+            return true;
+        }
+
+        // A prevote quorum for this round's block: lock on it and precommit
+        if self.step >= Step::PreVote
+            && !self.valid_block_updated
+            && let Some((block, hash, true)) = self.proposal(round)
+            && self.pre_votes.has_quorum(height, round, Some(&hash), &self.validators)
+        {
+            self.valid_block_updated = true;
+            if self.step == Step::PreVote {
+                self.locked = Some((round, block.clone()));
+                self.cast_vote(VoteType::PreCommit, Some(hash), outputs);
+                self.step = Step::PreCommit;
+            }
+            self.valid = Some((round, block));
+            return true;
+        }
+
+        if self.step == Step::PreVote && self.pre_votes.has_quorum(height, round, None, &self.validators) {
+            self.cast_vote(VoteType::PreCommit, None, outputs);
+            self.step = Step::PreCommit;
+            return true;
+        }
+
+        if !self.pre_commit_timeout_scheduled && self.pre_commits.has_any_quorum(height, round, &self.validators) {
+            self.pre_commit_timeout_scheduled = true;
+            self.schedule_timeout(Step::PreCommit, outputs);
+            return true;
+        }
+
+        false
+    }
+
+    // Latest round after ours in which validators holding more than a third of the stake voted
+    fn skip_round(&self) -> Option<u32> {
+        let total = self.validators.total_power();
+        let mut rounds = self.pre_votes.rounds(self.height);
+        rounds.extend(self.pre_commits.rounds(self.height));
+        rounds.into_iter().rev().filter(|round| *round > self.round).find(|round| {
+            let voters = self.pre_votes.voters(self.height, *round).chain(self.pre_commits.voters(self.height, *round));
+            let power = self.validators.power_of(voters);
+            power as u128 * 3 > total as u128
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::state::ValidatorInfo;
+
+    const PREV_BLOCK_HASH: &str = "parent";
+
+    // Four validators with equal stake: any three of them are a quorum, any two more than a third
+    struct Network {
+        engine: Tendermint,
+        keys: Vec<Arc<KeyPair>>,
+        /// Every validator but the one running `engine`
+        others: Vec<Arc<KeyPair>>,
+        validators: ValidatorSet,
+    }
+
+    impl Network {
+        // Runs the engine as a validator that does not propose in rounds 0 to 2, so the
+        // tests decide what gets proposed there
+        fn new() -> Self {
+            let keys: Vec<Arc<KeyPair>> = (0..4).map(|_| Arc::new(KeyPair::new())).collect();
+            let validators: ValidatorSet = keys
+                .iter()
+                .map(|key| ValidatorInfo { address: key.get_address(), stake: 10 })
+                .collect();
+            let proposers: Vec<String> = (0..3)
+                .filter_map(|round| edfm::select_proposer(&edfm::round_seed(PREV_BLOCK_HASH, round), &validators))
+                .collect();
+            let us = keys.iter().position(|key| !proposers.contains(&key.get_address())).unwrap();
+            let others = keys.iter().enumerate().filter(|(i, _)| *i != us).map(|(_, key)| Arc::clone(key)).collect();
+
+            let mut engine = Tendermint::new(Arc::clone(&keys[us]));
+            let outputs = engine.start_height(1, PREV_BLOCK_HASH.to_string(), validators.clone());
+            assert!(matches!(outputs[..], [Output::ScheduleTimeout(Timeout { step: Step::Propose, .. })]));
+            Self { engine, keys, others, validators }
+        }
+
+        // A block for height 1 first proposed in `round`; `nonce` tells blocks apart
+        fn block(&self, round: u32, nonce: u32) -> Block {
+            let mut block = Block::new(1, PREV_BLOCK_HASH.to_string(), 10, vec![]);
+            block.header.round = round;
+            block.header.nonce = nonce;
+            block
+        }
+
+        // Proposal of `round`, signed by its proposer
+        fn proposal(&self, round: u32, valid_round: Option<u32>, block: Block) -> Proposal {
+            let proposer = self.engine.proposer(round).unwrap();
+            let key = self.keys.iter().find(|key| key.get_address() == proposer).unwrap();
+            Proposal::new(1, round, valid_round, block, key)
+        }
+
+        fn propose(&mut self, round: u32, valid_round: Option<u32>, block: Block) -> Vec<Output> {
+            let proposal = self.proposal(round, valid_round, block);
+            self.engine.on_proposal(proposal, true).unwrap()
+        }
+
+        // Votes of the first `count` other validators
+        fn votes(&mut self, count: usize, vote_type: VoteType, round: u32, block_hash: Option<&str>) -> Vec<Output> {
+            let votes: Vec<Vote> = self.others[..count]
+                .iter()
+                .map(|key| Vote::new(vote_type, 1, round, block_hash.map(str::to_string), key))
+                .collect();
+            votes.into_iter().flat_map(|vote| self.engine.on_vote(vote).unwrap()).collect()
+        }
+
+        fn timeout(&mut self, step: Step) -> Vec<Output> {
+            self.engine.on_timeout(Timeout {
+                height: 1,
+                round: self.engine.round(),
+                step,
+                duration: Duration::ZERO,
+            })
+        }
+
+        // Round 0: locks on a block that then fails to get precommits, and moves on to round 1
+        fn lock_in_round_zero(&mut self) -> String {
+            let block = self.block(0, 0);
+            let hash = block.calculate_hash();
+            self.propose(0, None, block);
+            let outputs = self.votes(2, VoteType::PreVote, 0, Some(&hash));
+            assert_eq!(cast(&outputs, VoteType::PreCommit), vec![Some(hash.clone())]);
+            self.votes(2, VoteType::PreCommit, 0, None);
+            self.timeout(Step::PreCommit);
+            assert_eq!((self.engine.round(), self.engine.step()), (1, Step::Propose));
+            hash
+        }
+    }
+
+    // Block hashes of our own votes of `vote_type` among `outputs`
+    fn cast(outputs: &[Output], vote_type: VoteType) -> Vec<Option<String>> {
+        outputs
+            .iter()
+            .filter_map(|output| match output {
+                Output::Vote(vote) if vote.vote_type == vote_type => Some(vote.block_hash.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commits_a_block_with_prevote_and_precommit_quorums() {
+        let mut network = Network::new();
+        let block = network.block(0, 0);
+        let hash = block.calculate_hash();
+
+        let outputs = network.propose(0, None, block);
+        assert_eq!(cast(&outputs, VoteType::PreVote), vec![Some(hash.clone())]);
+
+        let outputs = network.votes(2, VoteType::PreVote, 0, Some(&hash));
+        assert_eq!(cast(&outputs, VoteType::PreCommit), vec![Some(hash.clone())]);
+        assert!(!network.engine.is_decided());
+
+        let outputs = network.votes(2, VoteType::PreCommit, 0, Some(&hash));
+        let committed = outputs.iter().find_map(|output| match output {
+            Output::Commit { block, round } => Some((block, *round)),
+            _ => None,
+        });
+        let (block, round) = committed.expect("block was not committed");
+        assert_eq!((block.calculate_hash(), round), (hash.clone(), 0));
+        assert!(network.engine.is_decided());
+
+        // The block carries the precommits that made it final
+        let commit = block.commit.as_ref().unwrap();
+        assert_eq!((commit.round, commit.votes.len()), (0, 3));
+        assert!(commit.verify(1, &hash, &network.validators).is_ok());
+    }
+
+    #[test]
+    fn prevotes_nil_when_no_proposal_arrives() {
+        let mut network = Network::new();
+        let outputs = network.timeout(Step::Propose);
+        assert_eq!(cast(&outputs, VoteType::PreVote), vec![None]);
+        assert_eq!(network.engine.step(), Step::PreVote);
+    }
+
+    #[test]
+    fn locked_validator_refuses_another_block_in_a_later_round() {
+        let mut network = Network::new();
+        network.lock_in_round_zero();
+
+        let other_block = network.block(1, 1);
+        let outputs = network.propose(1, None, other_block);
+        assert_eq!(cast(&outputs, VoteType::PreVote), vec![None]);
+    }
+
+    #[test]
+    fn unlocks_for_a_block_reproposed_with_a_later_valid_round() {
+        let mut network = Network::new();
+        let locked = network.lock_in_round_zero();
+
+        // Round 1: the others prevote another block we never saw proposed, nothing is committed
+        let other_block = network.block(1, 1);
+        let other_hash = other_block.calculate_hash();
+        assert_ne!(other_hash, locked);
+        network.timeout(Step::Propose);
+        network.votes(3, VoteType::PreVote, 1, Some(&other_hash));
+        let outputs = network.timeout(Step::PreVote);
+        assert_eq!(cast(&outputs, VoteType::PreCommit), vec![None]);
+        network.votes(2, VoteType::PreCommit, 1, None);
+        network.timeout(Step::PreCommit);
+        assert_eq!(network.engine.round(), 2);
+
+        // Round 2: re-proposed with its round 1 prevote quorum, which is newer than our lock
+        let outputs = network.propose(2, Some(1), other_block);
+        assert_eq!(cast(&outputs, VoteType::PreVote), vec![Some(other_hash)]);
+    }
+
+    #[test]
+    fn rejects_a_block_claiming_another_round_than_its_proposal() {
+        let mut network = Network::new();
+
+        // The proposer of round 0 cannot pass off its block as one of a round it picked
+        let proposal = network.proposal(0, None, network.block(5, 0));
+        assert!(matches!(network.engine.on_proposal(proposal, true), Err(Error::InvalidProposal { .. })));
+
+        // A re-proposed block keeps the round it was first proposed in, which is no later than its valid round
+        let proposal = network.proposal(3, Some(1), network.block(2, 0));
+        assert!(matches!(network.engine.on_proposal(proposal, true), Err(Error::InvalidProposal { .. })));
+        let proposal = network.proposal(3, Some(1), network.block(1, 0));
+        assert!(network.engine.on_proposal(proposal, true).is_ok());
+    }
+
+    #[test]
+    fn skips_to_a_round_with_more_than_a_third_of_the_stake() {
+        let mut network = Network::new();
+
+        network.votes(1, VoteType::PreVote, 3, None);
+        assert_eq!(network.engine.round(), 0);
+
+        network.votes(2, VoteType::PreVote, 3, None);
+        assert_eq!(network.engine.round(), 3);
+    }
+
+    #[test]
+    fn caps_timeouts_and_stays_in_the_last_round() {
+        let mut network = Network::new();
+
+        let outputs = network.votes(2, VoteType::PreVote, u32::MAX, None);
+        assert_eq!(network.engine.round(), u32::MAX);
+        assert!(outputs.iter().any(|output| matches!(output, Output::ScheduleTimeout(Timeout { duration, .. }) if *duration == MAX_TIMEOUT)));
+
+        assert!(network.timeout(Step::PreCommit).is_empty());
+        assert_eq!(network.engine.round(), u32::MAX);
+    }
+}
@@ -1,9 +1,16 @@
 // src/core/consensus/validator.rs
 
 use crate::core::block::Block;
+use crate::core::consensus::{edfm, fees, forks};
+use crate::core::execution;
+use crate::core::genesis;
+use crate::core::state::State;
 
-/// Validates a new block against the previous block
-pub fn validate_block(new_block: &Block, previous_block: &Block) -> bool {
+/// Validates everything about a new block that can be checked against the previous block
+/// alone, without executing its transactions, under the rules in force at its height
+pub fn validate_header(new_block: &Block, previous_block: &Block) -> bool {
+    let rules = forks::rules_at(new_block.header.block_number);
+    
     // Check block number sequence
     if new_block.header.block_number != previous_block.header.block_number + 1 {
         eprintln!("❌ Invalid block number: expected {}, got {}", 
@@ -23,8 +30,8 @@ pub fn validate_block(new_block: &Block, previous_block: &Block) -> bool {
         .unwrap()
         .as_secs();
     
-    // Allow blocks from up to 10 seconds in the future
-    if new_block.header.timestamp > now + 10 {
+    // Allow blocks from slightly in the future
+    if new_block.header.timestamp > now + rules.max_future_block_time {
         eprintln!("❌ Timestamp is too far in the future");
         return false;
     }
@@ -35,7 +42,204 @@ pub fn validate_block(new_block: &Block, previous_block: &Block) -> bool {
         return false;
     }
     
-    // TODO: Add more validations (merkle root, transactions, etc.)
+    // The base fee must follow from the parent's fullness
+    let expected_base_fee = fees::next_base_fee(&previous_block.header);
+    if new_block.header.base_fee != expected_base_fee {
+        eprintln!("❌ Invalid base fee: expected {}, got {}", expected_base_fee, new_block.header.base_fee);
+        return false;
+    }
+    
+    // Blocks must stay within the gas and size limits so they can be relayed and processed by every peer
+    if new_block.header.gas_limit != genesis::block_gas_limit() {
+        eprintln!("❌ Invalid gas limit: expected {}, got {}", genesis::block_gas_limit(), new_block.header.gas_limit);
+        return false;
+    }
+    
+    let gas_used = Block::total_gas(&new_block.transactions);
+    if new_block.header.gas_used != gas_used {
+        eprintln!("❌ Invalid gas used: header says {}, transactions use {}", new_block.header.gas_used, gas_used);
+        return false;
+    }
+    
+    if gas_used > new_block.header.gas_limit {
+        eprintln!("❌ Block uses {} gas, above the limit of {}", gas_used, new_block.header.gas_limit);
+        return false;
+    }
+    
+    let block_size = new_block.size();
+    if block_size > rules.max_block_size {
+        eprintln!("❌ Block is {} bytes, above the limit of {}", block_size, rules.max_block_size);
+        return false;
+    }
+    
+    // The header must commit to exactly these transactions
+    if new_block.header.merkle_root != Block::compute_merkle_root(&new_block.transactions) {
+        eprintln!("❌ Merkle root does not match the block's transactions");
+        return false;
+    }
+    
+    true
+}
+
+/// Checks that a block is signed by its proposer, and that the proposer is the validator
+/// EDFM selects for the block's round from its parent hash and the validator set of `state`,
+/// the state the block applies to. The round is chosen by the proposer, so on its own this
+/// only holds for blocks going through consensus, which checks it against the round of the
+/// proposal; committed blocks are held to their commit round by `validate_commit`.
+pub fn validate_proposer(new_block: &Block, state: &State) -> bool {
+    let header = &new_block.header;
+    let seed = edfm::round_seed(&header.prev_block_hash, header.round);
+    let expected = edfm::select_proposer(&seed, state.validators());
+    if expected.as_deref() != Some(header.proposer.as_str()) {
+        eprintln!("❌ Invalid proposer {} for round {}: expected {:?}", header.proposer, header.round, expected);
+        return false;
+    }
+    
+    if !header.verify_signature() {
+        eprintln!("❌ Invalid proposer signature");
+        return false;
+    }
+    
+    true
+}
+
+/// Checks that a block comes with a commit certificate proving it final: precommits for it
+/// from validators holding more than two thirds of the stake of `state`, the state the block
+/// applies to. They must be from the round the block was proposed in or a later one, which
+/// keeps a proposer from picking a round it is selected for after the fact.
+pub fn validate_commit(new_block: &Block, state: &State) -> bool {
+    let Some(commit) = &new_block.commit else {
+        eprintln!("❌ Block #{} has no commit certificate", new_block.header.block_number);
+        return false;
+    };
+    
+    if commit.round < new_block.header.round {
+        eprintln!("❌ Block #{} claims round {} but was committed in round {}",
+                 new_block.header.block_number, new_block.header.round, commit.round);
+        return false;
+    }
+    
+    if let Err(e) = commit.verify(new_block.header.block_number, &new_block.calculate_hash(), state.validators()) {
+        eprintln!("❌ {}", e);
+        return false;
+    }
     
     true
-}
\ No newline at end of file
+}
+
+/// Validates a new block against the previous block and the state it would be applied to
+pub fn validate_block(new_block: &Block, previous_block: &Block, state: &State) -> bool {
+    if !validate_header(new_block, previous_block) || !validate_proposer(new_block, state) {
+        return false;
+    }
+    
+    // Transactions must apply cleanly: authorized by their sender (a single signature or a
+    // multisig quorum), correct nonces, fees covering the base fee and no overspending.
+    // The receipts and state roots in the header must match our own execution.
+    if let Err(e) = execution::apply_block(state, new_block) {
+        eprintln!("❌ Invalid block contents: {}", e);
+        return false;
+    }
+    
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::consensus::vote::{CommitCertificate, Vote, VoteType};
+    use crate::crypto::keys::KeyPair;
+
+    const PREV_BLOCK_HASH: &str = "parent";
+
+    // Four validators with equal stake, in the state a block at height 1 applies to
+    fn setup() -> (Vec<KeyPair>, State) {
+        let keys: Vec<KeyPair> = (0..4).map(|_| KeyPair::new()).collect();
+        let mut state = State::new();
+        for key in &keys {
+            state.bond(&key.get_address(), 10);
+        }
+        (keys, state)
+    }
+
+    fn proposer_key<'a>(keys: &'a [KeyPair], state: &State, round: u32) -> &'a KeyPair {
+        let proposer = edfm::select_proposer(&edfm::round_seed(PREV_BLOCK_HASH, round), state.validators()).unwrap();
+        keys.iter().find(|key| key.get_address() == proposer).unwrap()
+    }
+
+    // A block at height 1 claiming `round`, signed by `key`
+    fn signed_block(round: u32, key: &KeyPair) -> Block {
+        let mut block = Block::new(1, PREV_BLOCK_HASH.to_string(), 10, vec![]);
+        block.header.round = round;
+        block.sign(key);
+        block
+    }
+
+    // Precommits for `block` in `round` from the first `count` validators
+    fn commit(block: &Block, round: u32, keys: &[KeyPair], count: usize) -> CommitCertificate {
+        let hash = block.calculate_hash();
+        let votes = keys[..count]
+            .iter()
+            .map(|key| Vote::new(VoteType::PreCommit, 1, round, Some(hash.clone()), key))
+            .collect();
+        CommitCertificate { round, votes }
+    }
+
+    #[test]
+    fn accepts_the_proposer_of_the_block_round() {
+        let (keys, state) = setup();
+        for round in 0..4 {
+            let block = signed_block(round, proposer_key(&keys, &state, round));
+            assert!(validate_proposer(&block, &state));
+        }
+    }
+
+    #[test]
+    fn rejects_a_proposer_not_selected_for_the_block_round() {
+        let (keys, state) = setup();
+        let proposer = proposer_key(&keys, &state, 0);
+        let other = keys.iter().find(|key| key.get_address() != proposer.get_address()).unwrap();
+        assert!(!validate_proposer(&signed_block(0, other), &state));
+
+        // Naming the right proposer does not help without its signature
+        let mut block = signed_block(0, other);
+        block.header.proposer = proposer.get_address();
+        assert!(!validate_proposer(&block, &state));
+    }
+
+    #[test]
+    fn rejects_a_block_round_after_its_commit_round() {
+        let (keys, state) = setup();
+
+        // The proposer of round 3 claims that round for a block committed in round 0
+        let mut block = signed_block(3, proposer_key(&keys, &state, 3));
+        assert!(validate_proposer(&block, &state));
+        block.commit = Some(commit(&block, 0, &keys, 3));
+        assert!(!validate_commit(&block, &state));
+
+        block.commit = Some(commit(&block, 3, &keys, 3));
+        assert!(validate_commit(&block, &state));
+        // A re-proposed block is committed in a round after its own
+        block.commit = Some(commit(&block, 5, &keys, 3));
+        assert!(validate_commit(&block, &state));
+    }
+
+    #[test]
+    fn requires_a_precommit_quorum() {
+        let (keys, state) = setup();
+        let mut block = signed_block(0, proposer_key(&keys, &state, 0));
+        assert!(!validate_commit(&block, &state));
+
+        block.commit = Some(commit(&block, 0, &keys, 2));
+        assert!(!validate_commit(&block, &state));
+
+        // A repeated precommit does not count twice
+        let mut repeated = commit(&block, 0, &keys, 2);
+        repeated.votes.push(repeated.votes[0].clone());
+        block.commit = Some(repeated);
+        assert!(!validate_commit(&block, &state));
+
+        block.commit = Some(commit(&block, 0, &keys, 3));
+        assert!(validate_commit(&block, &state));
+    }
+}
@@ -0,0 +1,128 @@
+// src/core/consensus/validator_set.rs
+
+use crate::core::state::ValidatorInfo;
+use crate::crypto::hash::calculate_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Validators with their stake, kept sorted by address. Every node holding the same set
+/// sees it in the same order, so anything derived from that order (proposer selection,
+/// indices, the set hash) is identical across nodes. Validators never have zero stake.
+///
+/// Serialized as a map from address to validator, like the validator map of older states.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, ValidatorInfo>", into = "BTreeMap<String, ValidatorInfo>")]
+pub struct ValidatorSet {
+    validators: Vec<ValidatorInfo>,
+}
+
+impl ValidatorSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// Validators in canonical (address) order
+    pub fn iter(&self) -> std::slice::Iter<'_, ValidatorInfo> {
+        self.validators.iter()
+    }
+
+    /// Position of `address` in the canonical order
+    pub fn index_of(&self, address: &str) -> Option<usize> {
+        self.validators.binary_search_by(|v| v.address.as_str().cmp(address)).ok()
+    }
+
+    /// Validator at `index` in the canonical order
+    pub fn by_index(&self, index: usize) -> Option<&ValidatorInfo> {
+        self.validators.get(index)
+    }
+
+    pub fn get(&self, address: &str) -> Option<&ValidatorInfo> {
+        self.index_of(address).map(|index| &self.validators[index])
+    }
+
+    pub fn contains(&self, address: &str) -> bool {
+        self.index_of(address).is_some()
+    }
+
+    /// Stake of all validators together
+    pub fn total_power(&self) -> u64 {
+        self.validators.iter().fold(0, |total, v| total.saturating_add(v.stake))
+    }
+
+    /// Stake of the validators among `addresses`, each counted once
+    pub fn power_of<'a>(&self, addresses: impl IntoIterator<Item = &'a str>) -> u64 {
+        let mut indices: Vec<usize> = addresses.into_iter().filter_map(|address| self.index_of(address)).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices.iter().fold(0, |total, index| total.saturating_add(self.validators[*index].stake))
+    }
+
+    /// Hash identifying the set: its validators and stakes in canonical order
+    pub fn hash(&self) -> String {
+        calculate_hash(&self.validators)
+    }
+
+    /// Adds `amount` to the stake of `address`, adding it to the set if needed
+    pub fn add_stake(&mut self, address: &str, amount: u64) {
+        if amount == 0 {
+            return;
+        }
+        match self.validators.binary_search_by(|v| v.address.as_str().cmp(address)) {
+            Ok(index) => {
+                let validator = &mut self.validators[index];
+                validator.stake = validator.stake.saturating_add(amount);
+            }
+            Err(index) => self.validators.insert(
+                index,
+                ValidatorInfo {
+                    address: address.to_string(),
+                    stake: amount,
+                },
+            ),
+        }
+    }
+
+    /// Removes up to `amount` from the stake of `address`. A validator left without
+    /// stake leaves the set.
+    pub fn remove_stake(&mut self, address: &str, amount: u64) {
+        let Some(index) = self.index_of(address) else {
+            return;
+        };
+        let validator = &mut self.validators[index];
+        validator.stake = validator.stake.saturating_sub(amount);
+        if validator.stake == 0 {
+            self.validators.remove(index);
+        }
+    }
+}
+
+impl FromIterator<ValidatorInfo> for ValidatorSet {
+    /// Builds a set from validators in any order; stakes of a repeated address add up
+    fn from_iter<I: IntoIterator<Item = ValidatorInfo>>(validators: I) -> Self {
+        let mut set = Self::new();
+        for validator in validators {
+            set.add_stake(&validator.address, validator.stake);
+        }
+        set
+    }
+}
+
+impl From<BTreeMap<String, ValidatorInfo>> for ValidatorSet {
+    fn from(validators: BTreeMap<String, ValidatorInfo>) -> Self {
+        validators.into_values().collect()
+    }
+}
+
+impl From<ValidatorSet> for BTreeMap<String, ValidatorInfo> {
+    fn from(set: ValidatorSet) -> Self {
+        set.validators.into_iter().map(|v| (v.address.clone(), v)).collect()
+    }
+}
@@ -0,0 +1,215 @@
+// src/core/consensus/vote.rs
+
+use crate::core::consensus::validator_set::ValidatorSet;
+use crate::core::error::Error;
+use crate::core::genesis;
+use crate::crypto::hash::calculate_hash;
+use crate::crypto::keys::KeyPair;
+use crate::crypto::signature;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Step of the consensus round a vote belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VoteType {
+    PreVote,
+    PreCommit,
+}
+
+/// A validator's vote for a block at a given height and round, signed with its key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vote {
+    pub vote_type: VoteType,
+    pub height: u64,
+    pub round: u32,
+    /// Block voted for; None is a vote for no block in this round (nil)
+    pub block_hash: Option<String>,
+    /// Address of the validator casting the vote
+    pub validator: String,
+    pub signature: String,
+}
+
+impl Vote {
+    /// Creates a vote cast and signed by `keypair`
+    pub fn new(vote_type: VoteType, height: u64, round: u32, block_hash: Option<String>, keypair: &KeyPair) -> Self {
+        let mut vote = Self {
+            vote_type,
+            height,
+            round,
+            block_hash,
+            validator: keypair.get_address(),
+            signature: String::new(),
+        };
+        vote.signature = signature::sign_hash(&vote.signing_hash(), keypair);
+        vote
+    }
+
+    /// Digest the validator signs. It includes the chain ID, so a vote cannot be
+    /// replayed on another network.
+    pub fn signing_hash(&self) -> String {
+        calculate_hash(&(
+            genesis::chain_id(),
+            self.vote_type,
+            self.height,
+            self.round,
+            &self.block_hash,
+            &self.validator,
+        ))
+    }
+
+    /// Checks that the vote is signed by the validator it names
+    pub fn verify_signature(&self) -> bool {
+        signature::verify_signature(&self.signing_hash(), &self.signature, &self.validator)
+    }
+}
+
+/// Precommits for a block from validators holding more than two thirds of the stake, all
+/// cast in the round the block was decided in. Proves the block is final to nodes that did
+/// not follow that round, so it travels with the block, outside its hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitCertificate {
+    pub round: u32,
+    pub votes: Vec<Vote>,
+}
+
+impl CommitCertificate {
+    /// Checks that the certificate commits the block with `block_hash` at `height`:
+    /// validly signed precommits for it in `round` from distinct members of `validators`,
+    /// together holding more than two thirds of their stake
+    pub fn verify(&self, height: u64, block_hash: &str, validators: &ValidatorSet) -> Result<(), Error> {
+        let invalid = |reason: &str| Error::InvalidCommit {
+            height,
+            reason: reason.to_string(),
+        };
+        let mut signers: Vec<&str> = Vec::new();
+        for vote in &self.votes {
+            if vote.vote_type != VoteType::PreCommit || vote.height != height || vote.round != self.round {
+                return Err(invalid("vote is not a precommit of this height and round"));
+            }
+            if vote.block_hash.as_deref() != Some(block_hash) {
+                return Err(invalid("precommit for another block"));
+            }
+            if !validators.contains(&vote.validator) || signers.contains(&vote.validator.as_str()) {
+                return Err(invalid("precommit that is not from a distinct validator"));
+            }
+            if !vote.verify_signature() {
+                return Err(invalid("invalid precommit signature"));
+            }
+            signers.push(&vote.validator);
+        }
+        if !has_quorum(validators.power_of(signers), validators.total_power()) {
+            return Err(invalid("precommits hold no more than two thirds of the stake"));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `power` is more than two thirds of `total`
+pub fn has_quorum(power: u64, total: u64) -> bool {
+    power as u128 * 3 > total as u128 * 2
+}
+
+/// Votes of one type, at most one per validator for each height and round.
+/// Votes are weighted by the stake of their validator.
+#[derive(Debug)]
+pub struct VoteSet {
+    vote_type: VoteType,
+    votes: HashMap<(u64, u32, String), Vote>,
+}
+
+impl VoteSet {
+    pub fn new(vote_type: VoteType) -> Self {
+        Self {
+            vote_type,
+            votes: HashMap::new(),
+        }
+    }
+
+    /// Adds a vote after checking its type, its signature and that it comes from one of
+    /// `validators`. Returns false if the same vote was already known; a different vote
+    /// from the same validator for the same height and round is rejected as equivocation.
+    pub fn add(&mut self, vote: Vote, validators: &ValidatorSet) -> Result<bool, Error> {
+        let invalid = |reason: &str| Error::InvalidVote {
+            validator: vote.validator.clone(),
+            reason: reason.to_string(),
+        };
+        if vote.vote_type != self.vote_type {
+            return Err(invalid("wrong vote type"));
+        }
+        if !vote.verify_signature() {
+            return Err(invalid("invalid signature"));
+        }
+        if !validators.contains(&vote.validator) {
+            return Err(invalid("not a validator"));
+        }
+
+        let key = (vote.height, vote.round, vote.validator.clone());
+        if let Some(existing) = self.votes.get(&key) {
+            if existing.block_hash == vote.block_hash {
+                return Ok(false);
+            }
+            return Err(Error::ConflictingVote {
+                validator: vote.validator,
+                height: vote.height,
+                round: vote.round,
+            });
+        }
+        self.votes.insert(key, vote);
+        Ok(true)
+    }
+
+    /// Total stake of the validators that voted for `block_hash` (None for nil) at this height and round
+    pub fn voting_power(&self, height: u64, round: u32, block_hash: Option<&str>, validators: &ValidatorSet) -> u64 {
+        validators.power_of(
+            self.votes
+                .values()
+                .filter(|vote| vote.height == height && vote.round == round && vote.block_hash.as_deref() == block_hash)
+                .map(|vote| vote.validator.as_str()),
+        )
+    }
+
+    /// Whether validators holding more than two thirds of the stake voted for `block_hash`
+    pub fn has_quorum(&self, height: u64, round: u32, block_hash: Option<&str>, validators: &ValidatorSet) -> bool {
+        has_quorum(self.voting_power(height, round, block_hash, validators), validators.total_power())
+    }
+
+    /// Whether validators holding more than two thirds of the stake voted at this height
+    /// and round, for any block or nil
+    pub fn has_any_quorum(&self, height: u64, round: u32, validators: &ValidatorSet) -> bool {
+        has_quorum(validators.power_of(self.voters(height, round)), validators.total_power())
+    }
+
+    /// Votes for `block_hash` (None for nil) at this height and round, in validator order
+    pub fn votes_for(&self, height: u64, round: u32, block_hash: Option<&str>) -> Vec<Vote> {
+        let mut votes: Vec<Vote> = self
+            .votes
+            .values()
+            .filter(|vote| vote.height == height && vote.round == round && vote.block_hash.as_deref() == block_hash)
+            .cloned()
+            .collect();
+        votes.sort_by(|a, b| a.validator.cmp(&b.validator));
+        votes
+    }
+
+    /// Validators that voted at this height and round
+    pub fn voters(&self, height: u64, round: u32) -> impl Iterator<Item = &str> {
+        self.votes
+            .keys()
+            .filter(move |(vote_height, vote_round, _)| *vote_height == height && *vote_round == round)
+            .map(|(_, _, validator)| validator.as_str())
+    }
+
+    /// Rounds of `height` that have at least one vote
+    pub fn rounds(&self, height: u64) -> BTreeSet<u32> {
+        self.votes
+            .keys()
+            .filter(|(vote_height, _, _)| *vote_height == height)
+            .map(|(_, round, _)| *round)
+            .collect()
+    }
+
+    /// Drops every vote for heights below `height`
+    pub fn prune_below(&mut self, height: u64) {
+        self.votes.retain(|(vote_height, _, _), _| *vote_height >= height);
+    }
+}
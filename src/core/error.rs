@@ -0,0 +1,157 @@
+// src/core/error.rs
+
+use std::fmt;
+
+/// Reasons a transaction, block or consensus vote can be rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    InsufficientBalance {
+        address: String,
+        balance: u64,
+        required: u64,
+    },
+    InvalidNonce {
+        address: String,
+        expected: u64,
+        got: u64,
+    },
+    InvalidSignature {
+        tx_hash: String,
+    },
+    DuplicateTransaction {
+        tx_hash: String,
+    },
+    MempoolFull,
+    UnsupportedVersion {
+        tx_hash: String,
+        version: u8,
+    },
+    InvalidTransaction {
+        tx_hash: String,
+        reason: String,
+    },
+    InsufficientStake {
+        address: String,
+        staked: u64,
+        requested: u64,
+    },
+    UnknownContract {
+        address: String,
+    },
+    MultisigThresholdNotMet {
+        address: String,
+        signers: usize,
+        threshold: u32,
+    },
+    ReceiptsRootMismatch {
+        block_number: u64,
+        expected: String,
+        got: String,
+    },
+    StateRootMismatch {
+        block_number: u64,
+        expected: String,
+        got: String,
+    },
+    BatchCallFailed {
+        index: usize,
+        source: Box<Error>,
+    },
+    OutsideValidityWindow {
+        tx_hash: String,
+        block_number: u64,
+    },
+    WrongChain {
+        tx_hash: String,
+        expected: u64,
+        got: u64,
+    },
+    FeeTooLow {
+        tx_hash: String,
+        max_fee_per_gas: u64,
+        base_fee: u64,
+    },
+    InvalidVote {
+        validator: String,
+        reason: String,
+    },
+    ConflictingVote {
+        validator: String,
+        height: u64,
+        round: u32,
+    },
+    InvalidProposal {
+        height: u64,
+        round: u32,
+        reason: String,
+    },
+    InvalidCommit {
+        height: u64,
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InsufficientBalance { address, balance, required } => {
+                write!(f, "insufficient balance for {}: has {}, needs {}", address, balance, required)
+            }
+            Error::InvalidNonce { address, expected, got } => {
+                write!(f, "invalid nonce for {}: expected {}, got {}", address, expected, got)
+            }
+            Error::InvalidSignature { tx_hash } => {
+                write!(f, "invalid signature on transaction {}", tx_hash)
+            }
+            Error::DuplicateTransaction { tx_hash } => {
+                write!(f, "transaction {} is already known", tx_hash)
+            }
+            Error::MempoolFull => write!(f, "mempool is full"),
+            Error::UnsupportedVersion { tx_hash, version } => {
+                write!(f, "transaction {} has unsupported version {}", tx_hash, version)
+            }
+            Error::InvalidTransaction { tx_hash, reason } => {
+                write!(f, "invalid transaction {}: {}", tx_hash, reason)
+            }
+            Error::InsufficientStake { address, staked, requested } => {
+                write!(f, "insufficient stake for {}: has {}, requested {}", address, staked, requested)
+            }
+            Error::UnknownContract { address } => write!(f, "no contract deployed at {}", address),
+            Error::MultisigThresholdNotMet { address, signers, threshold } => {
+                write!(f, "multisig {} needs {} owner signatures, got {}", address, threshold, signers)
+            }
+            Error::ReceiptsRootMismatch { block_number, expected, got } => {
+                write!(f, "block #{} commits receipts root {}, execution gives {}", block_number, got, expected)
+            }
+            Error::StateRootMismatch { block_number, expected, got } => {
+                write!(f, "block #{} commits state root {}, execution gives {}", block_number, got, expected)
+            }
+            Error::BatchCallFailed { index, source } => {
+                write!(f, "batch call #{} failed: {}", index, source)
+            }
+            Error::OutsideValidityWindow { tx_hash, block_number } => {
+                write!(f, "transaction {} is not valid at block #{}", tx_hash, block_number)
+            }
+            Error::WrongChain { tx_hash, expected, got } => {
+                write!(f, "transaction {} is signed for chain {}, this is chain {}", tx_hash, got, expected)
+            }
+            Error::FeeTooLow { tx_hash, max_fee_per_gas, base_fee } => {
+                write!(f, "transaction {} caps its fee at {} per gas, below the base fee {}", tx_hash, max_fee_per_gas, base_fee)
+            }
+            Error::InvalidVote { validator, reason } => {
+                write!(f, "invalid vote from {}: {}", validator, reason)
+            }
+            Error::ConflictingVote { validator, height, round } => {
+                write!(f, "{} voted for two different blocks at height {} round {}", validator, height, round)
+            }
+            Error::InvalidProposal { height, round, reason } => {
+                write!(f, "invalid proposal at height {} round {}: {}", height, round, reason)
+            }
+            Error::InvalidCommit { height, reason } => {
+                write!(f, "invalid commit certificate for block #{}: {}", height, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,59 @@
+// src/core/execution.rs
+
+use crate::core::block::Block;
+use crate::core::error::Error;
+use crate::core::receipt::Receipt;
+use crate::core::state::{BlockContext, State};
+
+/// Runs the transactions of `block` on top of `state` without checking the roots in its header,
+/// then brings in the staking changes effective from the next block.
+/// Used by the producer to fill those roots in; everything else goes through `apply_block`.
+pub fn execute_block(state: &State, block: &Block) -> Result<(State, Vec<Receipt>), Error> {
+    let context = BlockContext::new(&block.header);
+    let mut next_state = state.clone();
+    let receipts = next_state.apply_transactions(&block.transactions, &context)?;
+    next_state.activate_stake_changes(block.header.block_number + 1);
+    Ok((next_state, receipts))
+}
+
+/// Like `apply_block`, but also returns the receipts of the block's transactions
+pub fn apply_block_with_receipts(state: &State, block: &Block) -> Result<(State, Vec<Receipt>), Error> {
+    let (next_state, receipts) = execute_block(state, block)?;
+
+    // The committed receipts must match our own execution of the block
+    let receipts_root = Receipt::compute_receipts_root(&receipts);
+    if block.header.receipts_root != receipts_root {
+        return Err(Error::ReceiptsRootMismatch {
+            block_number: block.header.block_number,
+            expected: receipts_root,
+            got: block.header.receipts_root.clone(),
+        });
+    }
+
+    // So must the resulting state, otherwise we would silently diverge from the proposer
+    let state_root = next_state.state_root();
+    if block.header.state_root != state_root {
+        return Err(Error::StateRootMismatch {
+            block_number: block.header.block_number,
+            expected: state_root,
+            got: block.header.state_root.clone(),
+        });
+    }
+
+    Ok((next_state, receipts))
+}
+
+/// The state transition function: the only way chain state changes.
+/// Pure and deterministic, so replaying the same blocks from the genesis state
+/// always yields the same state on every node.
+pub fn apply_block(state: &State, block: &Block) -> Result<State, Error> {
+    apply_block_with_receipts(state, block).map(|(next_state, _)| next_state)
+}
+
+/// Rebuilds the state at the tip of `blocks` (genesis first) by replaying them on `genesis_state`
+pub fn replay_blocks(genesis_state: &State, blocks: &[Block]) -> Result<State, Error> {
+    blocks
+        .iter()
+        .skip(1) // The genesis block carries no transactions, its state is given
+        .try_fold(genesis_state.clone(), |state, block| apply_block(&state, block))
+}
@@ -1,36 +1,179 @@
 // src/core/genesis.rs
 
 use crate::core::block::{Block, Header};
-use crate::crypto::hash::calculate_hash;
+use crate::core::consensus::fees::INITIAL_BASE_FEE;
+use crate::core::consensus::forks::ForkSchedule;
+use crate::core::consensus::limits::BLOCK_GAS_LIMIT;
+use crate::core::state::State;
+use crate::crypto::merkle::EMPTY_ROOT;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::sync::OnceLock;
 
-/// Returns the fixed genesis block for all nodes in the network
-/// This ensures all nodes start with the same initial state
-pub fn get_genesis_block() -> Block {
-    Block {
-        header: Header {
-            block_number: 0,
-            prev_block_hash: "0".to_string(),
-            timestamp: 1728151993, // Fixed timestamp: 2025-10-05 14:13:13 UTC
-            merkle_root: "0".to_string(),
-            nonce: 0,
-        },
-        transactions: vec![],
+/// Where the genesis spec of the network this node belongs to is read from
+pub const GENESIS_PATH: &str = "config/genesis.json";
+
+/// Initial balance of an address in the genesis state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisAllocation {
+    pub address: String,
+    pub balance: u64,
+}
+
+/// Validator of the genesis state with its stake. This is the only way to bootstrap a
+/// network: later validators join by staking. A node's address is printed on startup
+/// and stays the same across restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisValidator {
+    pub address: String,
+    pub stake: u64,
+}
+
+/// Consensus parameters fixed for the lifetime of a network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsensusParams {
+    /// Base fee of the genesis block, later ones follow `consensus::fees`
+    pub initial_base_fee: u64,
+    /// Gas limit every block header must carry
+    pub block_gas_limit: u64,
+    /// Seconds between block production attempts
+    pub block_time: u64,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self {
+            initial_base_fee: INITIAL_BASE_FEE,
+            block_gas_limit: BLOCK_GAS_LIMIT,
+            block_time: 10,
+        }
+    }
+}
+
+/// Everything that defines a network before its first block, as stored in `GENESIS_PATH`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisSpec {
+    /// Identifier of the network. It is part of every signed transaction payload,
+    /// so a transaction signed for another Erbium network is never valid here.
+    pub chain_id: u64,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub allocations: Vec<GenesisAllocation>,
+    #[serde(default)]
+    pub validators: Vec<GenesisValidator>,
+    #[serde(default)]
+    pub consensus: ConsensusParams,
+    /// Heights at which protocol upgrades activate, see `consensus::forks`
+    #[serde(default)]
+    pub forks: ForkSchedule,
+}
+
+impl GenesisSpec {
+    /// Reads and checks a genesis spec file
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+        let spec: Self = serde_json::from_str(&json).map_err(|e| format!("Could not parse {}: {}", path, e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Checks the parts of the spec that would otherwise be silently merged or ignored
+    pub fn validate(&self) -> Result<(), String> {
+        if self.consensus.block_gas_limit == 0 || self.consensus.block_time == 0 {
+            return Err("block_gas_limit and block_time must be positive".to_string());
+        }
+
+        let mut seen = HashSet::new();
+        for allocation in &self.allocations {
+            if !seen.insert(&allocation.address) {
+                return Err(format!("Duplicate allocation for {}", allocation.address));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for validator in &self.validators {
+            if validator.stake == 0 {
+                return Err(format!("Validator {} has no stake", validator.address));
+            }
+            if !seen.insert(&validator.address) {
+                return Err(format!("Duplicate validator {}", validator.address));
+            }
+        }
+        Ok(())
+    }
+
+    /// The state every node of this network starts from before applying block #1
+    pub fn state(&self) -> State {
+        let mut state = State::new();
+        for allocation in &self.allocations {
+            state.credit(&allocation.address, allocation.balance);
+        }
+        for validator in &self.validators {
+            state.bond(&validator.address, validator.stake);
+        }
+        state
+    }
+
+    /// The genesis block of this network, committing to `state()`
+    pub fn block(&self) -> Block {
+        Block {
+            header: Header {
+                block_number: 0,
+                prev_block_hash: "0".to_string(),
+                timestamp: self.timestamp,
+                merkle_root: EMPTY_ROOT.to_string(),
+                receipts_root: EMPTY_ROOT.to_string(),
+                state_root: self.state().state_root(),
+                base_fee: self.consensus.initial_base_fee,
+                gas_limit: self.consensus.block_gas_limit,
+                gas_used: 0,
+                nonce: 0,
+                proposer: String::new(),
+                round: 0,
+                signature: String::new(),
+            },
+            transactions: vec![],
+            commit: None,
+        }
     }
 }
 
-/// Calculate the actual hash of the genesis block
-/// Run this once to get the real hash, then hardcode it
-pub fn calculate_genesis_hash() -> String {
-    let genesis_block = get_genesis_block();
-    calculate_hash(&genesis_block)
+static GENESIS: OnceLock<GenesisSpec> = OnceLock::new();
+
+/// The genesis spec of this node's network, loaded from `GENESIS_PATH` on first use
+pub fn spec() -> &'static GenesisSpec {
+    GENESIS.get_or_init(|| {
+        GenesisSpec::load(GENESIS_PATH).unwrap_or_else(|e| panic!("❌ CRITICAL: Invalid genesis spec: {}", e))
+    })
+}
+
+/// Returns the chain ID transactions must be signed for
+pub fn chain_id() -> u64 {
+    spec().chain_id
 }
 
-/// Pre-calculated hash of the genesis block for verification
-/// This must be the same across all nodes in the network
+/// Gas limit of every block of this network
+pub fn block_gas_limit() -> u64 {
+    spec().consensus.block_gas_limit
+}
+
+/// Returns the state every node starts from before applying block #1
+pub fn get_genesis_state() -> State {
+    spec().state()
+}
+
+/// Returns the genesis block all nodes of the network share
+pub fn get_genesis_block() -> Block {
+    spec().block()
+}
+
+/// Hash of the genesis block, computed from the spec.
+/// This must be the same across all nodes in the network.
 pub fn get_genesis_hash() -> String {
-    // Run calculate_genesis_hash() once to get this value
-    // Then hardcode it here
-    "51ed27efee90a9340e9da466359d062f51332706dce89d06f5cb081dede47820".to_string()
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| get_genesis_block().calculate_hash()).clone()
 }
 
 /// Validates if a given block matches the expected genesis block
@@ -39,4 +182,4 @@ pub fn is_valid_genesis_block(block: &Block) -> bool {
     let expected_hash = get_genesis_hash();
     println!("🔍 Genesis block validation - Actual: {}, Expected: {}", actual_hash, expected_hash);
     actual_hash == expected_hash
-}
\ No newline at end of file
+}
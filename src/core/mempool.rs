@@ -0,0 +1,183 @@
+// src/core/mempool.rs
+
+use crate::core::consensus::{fees, forks};
+use crate::core::error::Error;
+use crate::core::genesis;
+use crate::core::state::{BlockContext, State};
+use crate::core::transaction::Transaction;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+/// Maximum number of pending transactions kept in memory
+pub const MAX_MEMPOOL_SIZE: usize = 10_000;
+
+/// Pool of signed transactions waiting to be included in a block.
+/// Transactions are grouped per sender and ordered by nonce, so the producer
+/// can always pick a gap-free sequence for every account.
+#[derive(Debug, Default)]
+pub struct Mempool {
+    by_sender: HashMap<String, BTreeMap<u64, Transaction>>,
+    len: usize,
+}
+
+// Heap entry used while selecting transactions: highest tip first, ties broken by hash
+// so every node builds the same block from the same pool.
+struct Candidate {
+    tip: u64,
+    hash: String,
+    tx: Transaction,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.tip.cmp(&other.tip).then_with(|| other.hash.cmp(&self.hash))
+    }
+}
+
+impl Mempool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Validates a transaction against the current state and adds it to the pool.
+    /// `next_block` is the number of the block being built next: transactions that expire
+    /// before it are rejected, those only valid later are kept until their window opens.
+    /// A pending transaction with the same sender and nonce is only replaced by one offering a higher tip.
+    pub fn add_transaction(&mut self, tx: Transaction, state: &State, next_block: u64) -> Result<(), Error> {
+        let tx_hash = tx.hash();
+        tx.validate_basic(&forks::rules_at(next_block))?;
+
+        // Transactions signed for another network can never be included here
+        if tx.chain_id != genesis::chain_id() {
+            return Err(Error::WrongChain {
+                tx_hash,
+                expected: genesis::chain_id(),
+                got: tx.chain_id,
+            });
+        }
+
+        if tx.is_expired_at(next_block) {
+            return Err(Error::OutsideValidityWindow {
+                tx_hash,
+                block_number: next_block,
+            });
+        }
+
+        state.verify_authorization(&tx)?;
+
+        let account = state.get_account(&tx.from);
+        if tx.nonce < account.nonce {
+            return Err(Error::InvalidNonce {
+                address: tx.from.clone(),
+                expected: account.nonce,
+                got: tx.nonce,
+            });
+        }
+
+        let required = tx.max_cost();
+        if account.balance < required {
+            return Err(Error::InsufficientBalance {
+                address: tx.from.clone(),
+                balance: account.balance,
+                required,
+            });
+        }
+
+        let pending = self.by_sender.entry(tx.from.clone()).or_default();
+        match pending.get(&tx.nonce) {
+            Some(existing) if existing.priority_fee_per_gas >= tx.priority_fee_per_gas => {
+                return Err(Error::DuplicateTransaction { tx_hash });
+            }
+            Some(_) => {}
+            None => {
+                if self.len >= MAX_MEMPOOL_SIZE {
+                    return Err(Error::MempoolFull);
+                }
+                self.len += 1;
+            }
+        }
+
+        pending.insert(tx.nonce, tx);
+        println!("📥 Transaction {} added to mempool ({} pending)", &tx_hash[..8], self.len);
+        Ok(())
+    }
+
+    /// Picks transactions for the block described by `context`, highest tip first, until
+    /// `gas_limit` or `size_limit` (in serialized bytes) would be exceeded, while keeping each
+    /// sender's nonces consecutive and affordable against `state`.
+    /// Transactions whose fee cap is below the block's base fee or whose validity window
+    /// has not opened yet stay in the pool, holding back their sender's later nonces.
+    pub fn select_transactions(&self, state: &State, context: &BlockContext, gas_limit: u64, size_limit: usize) -> Vec<Transaction> {
+        let mut simulated = state.clone();
+        let mut heap = BinaryHeap::new();
+
+        let push_next = |heap: &mut BinaryHeap<Candidate>, simulated: &State, sender: &str| {
+            let next_nonce = simulated.get_account(sender).nonce;
+            if let Some(tx) = self.by_sender.get(sender).and_then(|txs| txs.get(&next_nonce)) {
+                if !tx.is_valid_at(context.block_number) {
+                    return;
+                }
+                if let Some((_, tip)) = fees::fee_split(tx.max_fee_per_gas, tx.priority_fee_per_gas, context.base_fee) {
+                    heap.push(Candidate { tip, hash: tx.hash(), tx: tx.clone() });
+                }
+            }
+        };
+
+        for sender in self.by_sender.keys() {
+            push_next(&mut heap, &simulated, sender);
+        }
+
+        let mut selected = Vec::new();
+        let mut gas_used = 0;
+        let mut size = 0;
+        while let Some(candidate) = heap.pop() {
+            // A sender whose next transaction does not fit or can no longer pay
+            // is skipped along with its later nonces
+            let tx_gas = candidate.tx.gas();
+            let tx_size = candidate.tx.size() + 1; // Separator in the JSON array
+            if gas_used + tx_gas > gas_limit || size + tx_size > size_limit {
+                continue;
+            }
+            if simulated.apply_transaction(&candidate.tx, context).is_ok() {
+                gas_used += tx_gas;
+                size += tx_size;
+                push_next(&mut heap, &simulated, &candidate.tx.from);
+                selected.push(candidate.tx);
+            }
+        }
+        selected
+    }
+
+    /// Drops transactions whose nonce has already been used on chain and those
+    /// whose validity window closed before `next_block`
+    pub fn prune(&mut self, state: &State, next_block: u64) {
+        self.by_sender.retain(|sender, pending| {
+            let account_nonce = state.get_account(sender).nonce;
+            pending.retain(|nonce, tx| *nonce >= account_nonce && !tx.is_expired_at(next_block));
+            !pending.is_empty()
+        });
+        self.len = self.by_sender.values().map(|pending| pending.len()).sum();
+    }
+}
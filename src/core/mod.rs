@@ -3,6 +3,12 @@
 pub mod block;
 pub mod chain;
 pub mod consensus;
+pub mod error;
+pub mod execution;
+pub mod mempool;
+pub mod receipt;
 pub mod state;
 pub mod transaction;
-pub mod genesis;
\ No newline at end of file
+pub mod genesis;
+
+pub use execution::apply_block;
@@ -0,0 +1,52 @@
+// src/core/receipt.rs
+
+use crate::crypto::hash::calculate_hash;
+use crate::crypto::merkle;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of applying a single transaction in a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Fee taken from the sender, charged even if execution failed
+    pub fee_charged: u64,
+    /// Why execution failed, if it did
+    pub error: Option<String>,
+}
+
+impl Receipt {
+    pub fn success(tx_hash: String, block_number: u64, gas_used: u64, fee_charged: u64) -> Self {
+        Self {
+            tx_hash,
+            block_number,
+            success: true,
+            gas_used,
+            fee_charged,
+            error: None,
+        }
+    }
+
+    pub fn failure(tx_hash: String, block_number: u64, gas_used: u64, fee_charged: u64, error: String) -> Self {
+        Self {
+            tx_hash,
+            block_number,
+            success: false,
+            gas_used,
+            fee_charged,
+            error: Some(error),
+        }
+    }
+
+    pub fn hash(&self) -> String {
+        calculate_hash(self)
+    }
+
+    /// Merkle root over the receipt hashes, committed in the block header
+    pub fn compute_receipts_root(receipts: &[Receipt]) -> String {
+        let hashes: Vec<String> = receipts.iter().map(|receipt| receipt.hash()).collect();
+        merkle::merkle_root(&hashes)
+    }
+}
@@ -1,33 +1,502 @@
 // src/core/state.rs
 
+use crate::core::block::Header;
+use crate::core::consensus::staking::{self, StakeChange};
+use crate::core::consensus::forks::{self, Rules};
+use crate::core::consensus::fees;
+use crate::core::consensus::validator_set::ValidatorSet;
+use crate::core::error::Error;
+use crate::core::genesis;
+use crate::core::receipt::Receipt;
+use crate::core::transaction::{Transaction, TransactionKind};
+use crate::crypto::hash::calculate_hash;
+use crate::crypto::smt::{self, SparseMerkleProof, SparseMerkleTree};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ValidatorInfo {
     pub address: String,
     pub stake: u64,
 }
 
+/// Balance and transaction counter of a single address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    pub balance: u64,
+    /// Nonce the next transaction from this account must carry
+    pub nonce: u64,
+}
+
+/// A deployed contract. Its balance lives in `State.accounts` under the contract address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contract {
+    pub creator: String,
+    /// Hex encoded bytecode
+    pub code: String,
+}
+
+/// Block-level parameters transactions are applied under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockContext {
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub base_fee: u64,
+    /// Receives the priority tips; None burns them as well
+    pub proposer: Option<String>,
+    /// Consensus rules in force at `block_number`
+    pub rules: Rules,
+}
+
+impl BlockContext {
+    /// Context for applying a block with `header`.
+    /// Tips go to the proposer named in the header, which `validator::validate_proposer` checks.
+    pub fn new(header: &Header) -> Self {
+        Self {
+            chain_id: genesis::chain_id(),
+            block_number: header.block_number,
+            base_fee: header.base_fee,
+            proposer: Some(header.proposer.clone()).filter(|proposer| !proposer.is_empty()),
+            rules: forks::rules_at(header.block_number),
+        }
+    }
+}
+
+/// Spending policy of a multisig account: any `threshold` of `owners` must sign
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigPolicy {
+    pub owners: Vec<String>,
+    pub threshold: u32,
+}
+
+/// Fields are private so every write goes through a method that also updates the state
+/// tree; `touch` must follow any change to an entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StateData")]
 pub struct State {
-    pub validators: HashMap<String, ValidatorInfo>,
+    /// Validator set proposers are chosen from. Only changed by staking transactions,
+    /// through `pending_stake_changes`.
+    validators: ValidatorSet,
+    /// Staking changes by the height from which they are in effect, see `consensus::staking`
+    pending_stake_changes: BTreeMap<u64, Vec<StakeChange>>,
+    accounts: HashMap<String, Account>,
+    contracts: HashMap<String, Contract>,
+    multisigs: HashMap<String, MultisigPolicy>,
+    /// Hashes of `entries()`, kept up to date on every write. Not stored, but rebuilt
+    /// when a state is loaded.
+    #[serde(skip)]
+    tree: SparseMerkleTree,
+}
+
+// What a stored state deserializes into, before its tree is rebuilt
+#[derive(Deserialize)]
+struct StateData {
+    validators: ValidatorSet,
+    #[serde(default)]
+    pending_stake_changes: BTreeMap<u64, Vec<StakeChange>>,
+    #[serde(default)]
+    accounts: HashMap<String, Account>,
+    #[serde(default)]
+    contracts: HashMap<String, Contract>,
+    #[serde(default)]
+    multisigs: HashMap<String, MultisigPolicy>,
+}
+
+impl From<StateData> for State {
+    fn from(data: StateData) -> Self {
+        let mut state = Self {
+            validators: data.validators,
+            pending_stake_changes: data.pending_stake_changes,
+            accounts: data.accounts,
+            contracts: data.contracts,
+            multisigs: data.multisigs,
+            tree: SparseMerkleTree::new(),
+        };
+        for (key, value) in state.entries() {
+            state.tree.insert(&key, Self::leaf(&key, &value));
+        }
+        state
+    }
+}
+
+fn entry_value<T: Serialize>(item: &T) -> String {
+    serde_json::to_string(item).expect("Failed to serialize state entry.")
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
-            validators: HashMap::new(),
+            validators: ValidatorSet::new(),
+            pending_stake_changes: BTreeMap::new(),
+            accounts: HashMap::new(),
+            contracts: HashMap::new(),
+            multisigs: HashMap::new(),
+            tree: SparseMerkleTree::new(),
         }
     }
 
-    pub fn register_validator(&mut self, address: String, stake: u64) {
-        let validator_info = ValidatorInfo {
-            address: address.clone(),
-            stake,
+    /// Validator set proposers are chosen from
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Flattens the state into sorted key/value pairs, the leaves of the state tree.
+    /// Keys are namespaced by kind (`account:`, `validator:`, ...) and values are JSON.
+    pub fn entries(&self) -> BTreeMap<String, String> {
+        fn insert_all<T: Serialize>(entries: &mut BTreeMap<String, String>, prefix: &str, items: &HashMap<String, T>) {
+            for (address, item) in items {
+                entries.insert(format!("{}:{}", prefix, address), entry_value(item));
+            }
+        }
+
+        let mut entries = BTreeMap::new();
+        insert_all(&mut entries, "account", &self.accounts);
+        for validator in self.validators.iter() {
+            entries.insert(format!("validator:{}", validator.address), entry_value(validator));
+        }
+        insert_all(&mut entries, "contract", &self.contracts);
+        insert_all(&mut entries, "multisig", &self.multisigs);
+        for (height, changes) in &self.pending_stake_changes {
+            entries.insert(format!("pending_stake:{}", height), entry_value(changes));
+        }
+        entries
+    }
+
+    /// Current value of a single entry of `entries()`, None if it does not exist
+    pub fn entry(&self, key: &str) -> Option<String> {
+        let (kind, id) = key.split_once(':')?;
+        match kind {
+            "account" => self.accounts.get(id).map(entry_value),
+            "validator" => self.validators.get(id).map(entry_value),
+            "contract" => self.contracts.get(id).map(entry_value),
+            "multisig" => self.multisigs.get(id).map(entry_value),
+            "pending_stake" => id.parse().ok().and_then(|height: u64| self.pending_stake_changes.get(&height)).map(entry_value),
+            _ => None,
+        }
+    }
+
+    fn leaf(key: &str, value: &str) -> String {
+        calculate_hash(&(key, value))
+    }
+
+    // Brings the state tree in line with the current value of `key`
+    fn touch(&mut self, key: &str) {
+        match self.entry(key) {
+            Some(value) => self.tree.insert(key, Self::leaf(key, &value)),
+            None => self.tree.remove(key),
+        }
+    }
+
+    /// Root of the sparse Merkle tree over every state entry, committed in each block header
+    pub fn state_root(&self) -> String {
+        self.tree.root()
+    }
+
+    /// Proves the current value stored under `key` (e.g. `account:0x...`) against
+    /// `state_root`, or that there is none
+    pub fn prove(&self, key: &str) -> (Option<String>, SparseMerkleProof) {
+        (self.entry(key), self.tree.prove(key))
+    }
+
+    /// Checks a proof produced by `prove` against a state root taken from a block header
+    pub fn verify_proof(state_root: &str, key: &str, value: Option<&str>, proof: &SparseMerkleProof) -> bool {
+        let value_hash = value.map(|value| Self::leaf(key, value));
+        smt::verify_proof(state_root, key, value_hash.as_deref(), proof)
+    }
+
+    /// Returns the account for `address`, or an empty one if it has never been touched
+    pub fn get_account(&self, address: &str) -> Account {
+        self.accounts.get(address).cloned().unwrap_or_default()
+    }
+
+    /// Adds `amount` to the balance of `address`
+    pub fn credit(&mut self, address: &str, amount: u64) {
+        let account = self.accounts.entry(address.to_string()).or_default();
+        account.balance = account.balance.saturating_add(amount);
+        self.touch(&format!("account:{}", address));
+    }
+
+    /// Removes `amount` from the balance of `address`
+    fn debit(&mut self, address: &str, amount: u64) -> Result<(), Error> {
+        let balance = self.get_account(address).balance;
+        if balance < amount {
+            return Err(Error::InsufficientBalance {
+                address: address.to_string(),
+                balance,
+                required: amount,
+            });
+        }
+        self.accounts.entry(address.to_string()).or_default().balance -= amount;
+        self.touch(&format!("account:{}", address));
+        Ok(())
+    }
+
+    /// Adds `amount` to the active stake of `address`, making it a validator if needed
+    pub fn bond(&mut self, address: &str, amount: u64) {
+        self.validators.add_stake(address, amount);
+        self.touch(&format!("validator:{}", address));
+    }
+
+    // Queues a staking change to take effect at the height it is due
+    fn schedule_stake_change(&mut self, block_number: u64, change: StakeChange) {
+        let height = staking::effective_height(block_number);
+        self.pending_stake_changes.entry(height).or_default().push(change);
+        self.touch(&format!("pending_stake:{}", height));
+    }
+
+    /// Stake of `address` that can still be unbonded: its active stake minus what
+    /// pending unbonds already take away
+    fn unbondable_stake(&self, address: &str) -> u64 {
+        let staked = self.validators.get(address).map_or(0, |v| v.stake);
+        let unbonding: u64 = self
+            .pending_stake_changes
+            .values()
+            .flatten()
+            .filter_map(|change| match change {
+                StakeChange::Unbond { address: a, amount } if a == address => Some(*amount),
+                _ => None,
+            })
+            .sum();
+        staked.saturating_sub(unbonding)
+    }
+
+    /// Total stake of the validator set once every pending staking change is in effect.
+    /// Pending changes are never due later than one scheduled now.
+    fn stake_after_pending_changes(&self) -> u64 {
+        self.pending_stake_changes
+            .values()
+            .flatten()
+            .fold(self.validators.total_power(), |total, change| match change {
+                StakeChange::Bond { amount, .. } => total.saturating_add(*amount),
+                StakeChange::Unbond { amount, .. } => total.saturating_sub(*amount),
+            })
+    }
+
+    /// Applies the staking changes that are in effect from `block_number` on.
+    /// Run after every block so the state holds the validator set for the next one.
+    pub fn activate_stake_changes(&mut self, block_number: u64) {
+        let pending = self.pending_stake_changes.split_off(&(block_number + 1));
+        let due = std::mem::replace(&mut self.pending_stake_changes, pending);
+        for (height, changes) in due {
+            self.touch(&format!("pending_stake:{}", height));
+            for change in changes {
+                match change {
+                    StakeChange::Bond { address, amount } => self.bond(&address, amount),
+                    StakeChange::Unbond { address, amount } => {
+                        self.validators.remove_stake(&address, amount);
+                        self.touch(&format!("validator:{}", address));
+                        self.credit(&address, amount);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that a transaction is authorized by its sender: a single valid signature for
+    /// plain accounts, or at least `threshold` distinct owner signatures for multisig accounts.
+    /// Signatures are outside the signed payload and every owner signature is charged gas,
+    /// so anything a relayer could add (unused fields, junk or repeated owner signatures)
+    /// is rejected rather than ignored.
+    pub fn verify_authorization(&self, tx: &Transaction) -> Result<(), Error> {
+        let invalid = |reason: &str| Error::InvalidTransaction {
+            tx_hash: tx.hash(),
+            reason: reason.to_string(),
+        };
+        let Some(policy) = self.multisigs.get(&tx.from) else {
+            if !tx.signatures.is_empty() {
+                return Err(invalid("owner signatures on a transaction from a plain account"));
+            }
+            if tx.verify_signature() {
+                return Ok(());
+            }
+            return Err(Error::InvalidSignature { tx_hash: tx.hash() });
         };
-        self.validators.insert(address, validator_info);
-        println!("DEBUG: Current validator set in State: {:?}", self.validators.keys());
-        println!("State updated. Total validators: {}", self.validators.len());
+
+        if !tx.signature.is_empty() {
+            return Err(invalid("single signature on a transaction from a multisig account"));
+        }
+        if tx.signatures.len() > policy.owners.len() {
+            return Err(invalid("more signatures than the account has owners"));
+        }
+        let mut signers: Vec<String> = Vec::new();
+        for signer in tx.owner_signers() {
+            match signer {
+                Some(signer) if policy.owners.contains(&signer) && !signers.contains(&signer) => signers.push(signer),
+                _ => return Err(invalid("signature that is not from a distinct owner")),
+            }
+        }
+
+        if signers.len() < policy.threshold as usize {
+            return Err(Error::MultisigThresholdNotMet {
+                address: tx.from.clone(),
+                signers: signers.len(),
+                threshold: policy.threshold,
+            });
+        }
+        Ok(())
+    }
+
+    /// Applies a single transaction. Its authorization and the sender's nonce are checked
+    /// and its fee charged first: the base fee per gas is burned and the tip paid to the
+    /// proposer. Then the kind-specific effects are executed.
+    /// An `Err` means the transaction may not be included in a block at all, while a
+    /// failed execution still charges the fee and is reported in the receipt.
+    pub fn apply_transaction(&mut self, tx: &Transaction, context: &BlockContext) -> Result<Receipt, Error> {
+        tx.validate_basic(&context.rules)?;
+        if tx.chain_id != context.chain_id {
+            return Err(Error::WrongChain {
+                tx_hash: tx.hash(),
+                expected: context.chain_id,
+                got: tx.chain_id,
+            });
+        }
+        if !tx.is_valid_at(context.block_number) {
+            return Err(Error::OutsideValidityWindow {
+                tx_hash: tx.hash(),
+                block_number: context.block_number,
+            });
+        }
+        self.verify_authorization(tx)?;
+        let sender = self.get_account(&tx.from);
+
+        let Some((burned, tip)) = fees::fee_split(tx.max_fee_per_gas, tx.priority_fee_per_gas, context.base_fee) else {
+            return Err(Error::FeeTooLow {
+                tx_hash: tx.hash(),
+                max_fee_per_gas: tx.max_fee_per_gas,
+                base_fee: context.base_fee,
+            });
+        };
+        let gas = tx.gas();
+        let fee = (burned + tip).saturating_mul(gas);
+
+        if tx.nonce != sender.nonce {
+            return Err(Error::InvalidNonce {
+                address: tx.from.clone(),
+                expected: sender.nonce,
+                got: tx.nonce,
+            });
+        }
+
+        // The sender must be able to cover its fee cap and everything it sends upfront
+        let required = tx.max_cost();
+        if sender.balance < required {
+            return Err(Error::InsufficientBalance {
+                address: tx.from.clone(),
+                balance: sender.balance,
+                required,
+            });
+        }
+
+        let sender = self.accounts.entry(tx.from.clone()).or_default();
+        sender.balance -= fee;
+        sender.nonce += 1;
+        self.touch(&format!("account:{}", tx.from));
+        if let Some(proposer) = &context.proposer {
+            self.credit(proposer, tip.saturating_mul(gas));
+        }
+
+        match self.execute(tx, &tx.kind, context.block_number) {
+            Ok(()) => Ok(Receipt::success(tx.hash(), context.block_number, gas, fee)),
+            Err(e) => Ok(Receipt::failure(tx.hash(), context.block_number, gas, fee, e.to_string())),
+        }
     }
-}
\ No newline at end of file
+
+    // Runs the effects of `kind` for a transaction whose fee has already been paid.
+    // Every branch checks before it mutates, so a failure leaves the state untouched.
+    fn execute(&mut self, tx: &Transaction, kind: &TransactionKind, block_number: u64) -> Result<(), Error> {
+        match kind {
+            TransactionKind::Transfer { to, value } => {
+                self.debit(&tx.from, *value)?;
+                self.credit(to, *value);
+            }
+            // Staking locks or releases funds now, the validator set follows at the effective height
+            TransactionKind::Stake { amount } => {
+                self.debit(&tx.from, *amount)?;
+                self.schedule_stake_change(block_number, StakeChange::Bond { address: tx.from.clone(), amount: *amount });
+            }
+            TransactionKind::Unstake { amount } => {
+                let staked = self.unbondable_stake(&tx.from);
+                if staked < *amount {
+                    return Err(Error::InsufficientStake {
+                        address: tx.from.clone(),
+                        staked,
+                        requested: *amount,
+                    });
+                }
+                // Without validators no block can ever be proposed again
+                if self.stake_after_pending_changes() <= *amount {
+                    return Err(Error::InvalidTransaction {
+                        tx_hash: tx.hash(),
+                        reason: "unbonding would leave no validators".to_string(),
+                    });
+                }
+                self.schedule_stake_change(block_number, StakeChange::Unbond { address: tx.from.clone(), amount: *amount });
+            }
+            TransactionKind::Deploy { code } => {
+                let address = tx.created_address().expect("Deploy always creates an address");
+                self.contracts.insert(address.clone(), Contract {
+                    creator: tx.from.clone(),
+                    code: code.clone(),
+                });
+                self.touch(&format!("contract:{}", address));
+            }
+            TransactionKind::Call { contract, value, .. } => {
+                // There is no VM yet, so a call only moves value into the contract
+                if !self.contracts.contains_key(contract) {
+                    return Err(Error::UnknownContract { address: contract.clone() });
+                }
+                self.debit(&tx.from, *value)?;
+                self.credit(contract, *value);
+            }
+            TransactionKind::CreateMultisig { owners, threshold } => {
+                let address = tx.created_address().expect("CreateMultisig always creates an address");
+                self.multisigs.insert(address.clone(), MultisigPolicy {
+                    owners: owners.clone(),
+                    threshold: *threshold,
+                });
+                self.touch(&format!("multisig:{}", address));
+            }
+            TransactionKind::Batch { calls } => {
+                // Batches only hold transfers and calls, which only move balances. Remember
+                // the accounts the calls touch and put them back if one of them fails.
+                let mut journal: HashMap<String, Option<Account>> = HashMap::new();
+                for (index, call) in calls.iter().enumerate() {
+                    let recipient = match call {
+                        TransactionKind::Transfer { to, .. } => to,
+                        TransactionKind::Call { contract, .. } => contract,
+                        _ => unreachable!("batches may only contain transfers and calls"),
+                    };
+                    for address in [&tx.from, recipient] {
+                        if !journal.contains_key(address) {
+                            journal.insert(address.clone(), self.accounts.get(address).cloned());
+                        }
+                    }
+
+                    if let Err(e) = self.execute(tx, call, block_number) {
+                        for (address, account) in journal {
+                            match account {
+                                Some(account) => self.accounts.insert(address.clone(), account),
+                                None => self.accounts.remove(&address),
+                            };
+                            self.touch(&format!("account:{}", address));
+                        }
+                        return Err(Error::BatchCallFailed {
+                            index,
+                            source: Box::new(e),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies every transaction of a block in order and returns their receipts,
+    /// stopping at the first transaction that makes the block invalid
+    pub fn apply_transactions(&mut self, transactions: &[Transaction], context: &BlockContext) -> Result<Vec<Receipt>, Error> {
+        transactions
+            .iter()
+            .map(|tx| self.apply_transaction(tx, context))
+            .collect()
+    }
+}
@@ -1,13 +1,298 @@
 // src/core/transaction.rs
 
+use crate::core::consensus::forks::Rules;
+use crate::core::consensus::limits;
+use crate::core::error::Error;
+use crate::core::genesis;
+use crate::crypto::hash::calculate_hash;
+use crate::crypto::keys::KeyPair;
+use crate::crypto::signature;
 use serde::{Deserialize, Serialize};
 
+/// Envelope version produced by this node. Bump it whenever the signed payload changes shape.
+pub const TRANSACTION_VERSION: u8 = 1;
+
+/// What a transaction does once its fee has been paid
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum TransactionKind {
+    /// Moves `value` from the sender to `to`
+    Transfer { to: String, value: u64 },
+    /// Locks `amount` of the sender's balance as validator stake
+    Stake { amount: u64 },
+    /// Releases `amount` of the sender's stake back into its balance
+    Unstake { amount: u64 },
+    /// Creates a contract with the given hex encoded bytecode
+    Deploy { code: String },
+    /// Calls a deployed contract, sending it `value` along with hex encoded input `data`
+    Call { contract: String, value: u64, data: String },
+    /// Creates an M-of-N multisig account controlled by `owners`
+    CreateMultisig { owners: Vec<String>, threshold: u32 },
+    /// Runs several transfers and calls all-or-nothing under one signature and fee
+    Batch { calls: Vec<TransactionKind> },
+}
+
+impl TransactionKind {
+    // Amount that leaves the sender's balance when this is executed.
+    pub fn value(&self) -> u64 {
+        match self {
+            TransactionKind::Transfer { value, .. } | TransactionKind::Call { value, .. } => *value,
+            TransactionKind::Stake { amount } => *amount,
+            TransactionKind::Unstake { .. }
+            | TransactionKind::Deploy { .. }
+            | TransactionKind::CreateMultisig { .. } => 0,
+            TransactionKind::Batch { calls } => calls
+                .iter()
+                .fold(0, |total: u64, call| total.saturating_add(call.value())),
+        }
+    }
+
+    // Gas for executing this kind, on top of the base gas every transaction pays.
+    pub fn gas(&self) -> u64 {
+        let data_gas = |hex: &str| (hex.len() as u64 / 2) * limits::DATA_BYTE_GAS;
+        match self {
+            TransactionKind::Transfer { .. } => 0,
+            TransactionKind::Stake { .. } | TransactionKind::Unstake { .. } => limits::STAKING_GAS,
+            TransactionKind::Deploy { code } => limits::DEPLOY_GAS + data_gas(code),
+            TransactionKind::Call { data, .. } => data_gas(data),
+            TransactionKind::CreateMultisig { owners, .. } => {
+                limits::ACCOUNT_CREATION_GAS + owners.len() as u64 * limits::SIGNATURE_GAS
+            }
+            TransactionKind::Batch { calls } => calls
+                .iter()
+                .map(|call| limits::BATCH_CALL_GAS + call.gas())
+                .sum(),
+        }
+    }
+
+    // Stateless payload checks. Returns why the payload is malformed, if it is.
+    fn payload_error(&self) -> Option<&'static str> {
+        match self {
+            TransactionKind::Transfer { to, .. } if to.is_empty() => Some("transfer without recipient"),
+            TransactionKind::Stake { amount: 0 } => Some("stake amount must be positive"),
+            TransactionKind::Unstake { amount: 0 } => Some("unstake amount must be positive"),
+            TransactionKind::Deploy { code } if code.is_empty() || hex::decode(code).is_err() => {
+                Some("contract code must be non-empty hex")
+            }
+            TransactionKind::Call { contract, .. } if contract.is_empty() => Some("call without contract"),
+            TransactionKind::Call { data, .. } if hex::decode(data).is_err() => Some("call data must be hex"),
+            TransactionKind::CreateMultisig { owners, threshold } => {
+                let mut unique = owners.clone();
+                unique.sort();
+                unique.dedup();
+                if *threshold == 0 || *threshold as usize > owners.len() {
+                    Some("threshold must be between 1 and the number of owners")
+                } else if unique.len() != owners.len() {
+                    Some("multisig owners must be distinct")
+                } else {
+                    None
+                }
+            }
+            TransactionKind::Batch { calls } if calls.is_empty() => Some("empty batch"),
+            TransactionKind::Batch { calls } => calls.iter().find_map(|call| match call {
+                TransactionKind::Transfer { .. } | TransactionKind::Call { .. } => call.payload_error(),
+                _ => Some("batches may only contain transfers and calls"),
+            }),
+            _ => None,
+        }
+    }
+}
+
 // THIS IS THE FIX: Add `Clone` to the derive macro here as well.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Transaction {
+    pub version: u8,
+    /// Network the transaction is meant for, see `genesis::chain_id`
+    pub chain_id: u64,
     pub from: String,
-    pub to: String,
-    pub value: u64,
-    pub fee: u64,
+    pub nonce: u64,
+    /// Most the sender is willing to pay per unit of gas (base fee plus tip)
+    pub max_fee_per_gas: u64,
+    /// Tip per unit of gas offered to the block proposer on top of the base fee
+    pub priority_fee_per_gas: u64,
+    pub kind: TransactionKind,
+    /// Only valid in blocks with a number strictly greater than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_after: Option<u64>,
+    /// Only valid in blocks with a number up to and including this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
     pub signature: String,
-}
\ No newline at end of file
+    /// Owner signatures when `from` is a multisig account; `signature` is unused then
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<String>,
+}
+
+// The part of a transaction covered by the signature: every field except the signature itself.
+#[derive(Serialize)]
+struct SigningPayload<'a> {
+    version: u8,
+    chain_id: u64,
+    from: &'a str,
+    nonce: u64,
+    max_fee_per_gas: u64,
+    priority_fee_per_gas: u64,
+    kind: &'a TransactionKind,
+    valid_after: Option<u64>,
+    valid_until: Option<u64>,
+}
+
+impl Transaction {
+    // Creates a new, unsigned transaction of any kind for this node's chain.
+    pub fn new(from: String, kind: TransactionKind, max_fee_per_gas: u64, priority_fee_per_gas: u64, nonce: u64) -> Self {
+        Self {
+            version: TRANSACTION_VERSION,
+            chain_id: genesis::chain_id(),
+            from,
+            nonce,
+            max_fee_per_gas,
+            priority_fee_per_gas,
+            kind,
+            valid_after: None,
+            valid_until: None,
+            signature: String::new(),
+            signatures: Vec::new(),
+        }
+    }
+
+    // Shorthand for the most common kind, a plain transfer.
+    pub fn transfer(from: String, to: String, value: u64, max_fee_per_gas: u64, priority_fee_per_gas: u64, nonce: u64) -> Self {
+        Self::new(from, TransactionKind::Transfer { to, value }, max_fee_per_gas, priority_fee_per_gas, nonce)
+    }
+
+    // The canonical digest that gets signed.
+    pub fn signing_hash(&self) -> String {
+        calculate_hash(&SigningPayload {
+            version: self.version,
+            chain_id: self.chain_id,
+            from: &self.from,
+            nonce: self.nonce,
+            max_fee_per_gas: self.max_fee_per_gas,
+            priority_fee_per_gas: self.priority_fee_per_gas,
+            kind: &self.kind,
+            valid_after: self.valid_after,
+            valid_until: self.valid_until,
+        })
+    }
+
+    // The transaction id, which also covers the signature.
+    pub fn hash(&self) -> String {
+        calculate_hash(self)
+    }
+
+    // Signs the transaction. The key pair must belong to the `from` address.
+    pub fn sign(&mut self, keypair: &KeyPair) {
+        self.signature = signature::sign_hash(&self.signing_hash(), keypair);
+    }
+
+    // Adds one owner's signature to a transaction sent from a multisig account.
+    pub fn sign_as_owner(&mut self, keypair: &KeyPair) {
+        self.signatures.push(signature::sign_hash(&self.signing_hash(), keypair));
+    }
+
+    // Addresses recovered from the owner signatures, in order; None for a malformed one.
+    pub fn owner_signers(&self) -> Vec<Option<String>> {
+        let signing_hash = self.signing_hash();
+        self.signatures
+            .iter()
+            .map(|sig| signature::recover_address(&signing_hash, sig))
+            .collect()
+    }
+
+    // Checks that the signature recovers to the `from` address.
+    pub fn verify_signature(&self) -> bool {
+        signature::verify_signature(&self.signing_hash(), &self.signature, &self.from)
+    }
+
+    // Restricts the block heights the transaction may be included at. Must be set before signing.
+    pub fn with_validity_window(mut self, valid_after: Option<u64>, valid_until: Option<u64>) -> Self {
+        self.valid_after = valid_after;
+        self.valid_until = valid_until;
+        self
+    }
+
+    // Whether the transaction may be included in block `block_number`.
+    pub fn is_valid_at(&self, block_number: u64) -> bool {
+        self.valid_after.is_none_or(|after| block_number > after)
+            && !self.is_expired_at(block_number)
+    }
+
+    // Whether the validity window closed before block `block_number`, for good.
+    pub fn is_expired_at(&self, block_number: u64) -> bool {
+        self.valid_until.is_some_and(|until| block_number > until)
+    }
+
+    // Amount that leaves the sender's balance on top of the fee.
+    pub fn value(&self) -> u64 {
+        self.kind.value()
+    }
+
+    // Gas consumed by the transaction. There is no VM metering yet, so this is
+    // fully determined by the kind and the size of its payload.
+    pub fn gas(&self) -> u64 {
+        // Every owner signature beyond the first costs an extra recovery
+        let signature_gas = self.signatures.len().saturating_sub(1) as u64 * limits::SIGNATURE_GAS;
+        limits::TX_BASE_GAS + self.kind.gas() + signature_gas
+    }
+
+    // Most this transaction can take from the sender's balance: value plus the fee cap for its gas.
+    pub fn max_cost(&self) -> u64 {
+        self.max_fee_per_gas
+            .saturating_mul(self.gas())
+            .saturating_add(self.value())
+    }
+
+    // Size of the transaction as it is relayed and stored.
+    pub fn size(&self) -> usize {
+        serde_json::to_string(self).map_or(usize::MAX, |json| json.len())
+    }
+
+    // Address of the contract or multisig account created by this transaction,
+    // derived from the sender and its nonce.
+    pub fn created_address(&self) -> Option<String> {
+        match self.kind {
+            TransactionKind::Deploy { .. } | TransactionKind::CreateMultisig { .. } => {
+                let hash = calculate_hash(&(&self.from, self.nonce));
+                Some(format!("0x{}", &hash[hash.len() - 40..]))
+            }
+            _ => None,
+        }
+    }
+
+    // Stateless checks on the envelope and its payload. A transaction failing
+    // these can never be included in a block under `rules`.
+    pub fn validate_basic(&self, rules: &Rules) -> Result<(), Error> {
+        if self.version != TRANSACTION_VERSION {
+            return Err(Error::UnsupportedVersion {
+                tx_hash: self.hash(),
+                version: self.version,
+            });
+        }
+
+        if let (Some(after), Some(until)) = (self.valid_after, self.valid_until)
+            && until <= after
+        {
+            return Err(Error::InvalidTransaction {
+                tx_hash: self.hash(),
+                reason: "validity window is empty".to_string(),
+            });
+        }
+
+        let reason = if let Some(reason) = self.kind.payload_error() {
+            Some(reason)
+        } else if self.gas() > genesis::block_gas_limit() {
+            Some("gas exceeds the block gas limit")
+        } else if self.size() > rules.max_transaction_size {
+            Some("transaction too large")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => Err(Error::InvalidTransaction {
+                tx_hash: self.hash(),
+                reason: reason.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
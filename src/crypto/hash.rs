@@ -20,4 +20,9 @@ pub fn calculate_hash<T: Serialize>(data: &T) -> String {
     
     // Format the hash result as a hexadecimal string and return it.
     format!("{:x}", hash_result)
-}
\ No newline at end of file
+}
+// Hashes raw bytes with SHA-256 and returns the hex digest.
+// Unlike `calculate_hash`, no JSON serialization happens first.
+pub fn hash_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
@@ -21,10 +21,15 @@ impl KeyPair {
     // Derives the Erbium address from the public key.
     // For now, it's just a hex representation of the public key.
     pub fn get_address(&self) -> String {
-        // We take the first 20 bytes (40 hex chars) for a shorter address, similar to Ethereum.
-        let full_hex = hex::encode(self.public_key.serialize_uncompressed());
-        format!("0x{}", &full_hex[full_hex.len()-40..])
+        public_key_to_address(&self.public_key)
     }
 }
 
+// Derives an Erbium address from any public key, e.g. one recovered from a signature.
+pub fn public_key_to_address(public_key: &PublicKey) -> String {
+    // We take the last 20 bytes (40 hex chars) for a shorter address, similar to Ethereum.
+    let full_hex = hex::encode(public_key.serialize_uncompressed());
+    format!("0x{}", &full_hex[full_hex.len()-40..])
+}
+
 // We need the `hex` crate for this. Add `hex = "0.4"` to your Cargo.toml dependencies.
\ No newline at end of file
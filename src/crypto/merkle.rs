@@ -0,0 +1,196 @@
+// src/crypto/merkle.rs
+
+use crate::crypto::hash::hash_bytes;
+use serde::{Deserialize, Serialize};
+
+// Root of a tree without leaves, e.g. a block without transactions.
+pub const EMPTY_ROOT: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Leaves and inner nodes get different prefixes so an inner node can never be
+// passed off as a leaf (second preimage protection).
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const ROOT_PREFIX: u8 = 0x02;
+
+fn hash_leaf(leaf: &str) -> String {
+    let mut data = vec![LEAF_PREFIX];
+    data.extend_from_slice(leaf.as_bytes());
+    hash_bytes(&data)
+}
+
+fn hash_node(left: &str, right: &str) -> String {
+    let mut data = vec![NODE_PREFIX];
+    data.extend_from_slice(left.as_bytes());
+    data.extend_from_slice(right.as_bytes());
+    hash_bytes(&data)
+}
+
+// The root commits to the number of leaves as well as the tree. The shape of the tree
+// depends on that number, so a proof is only meaningful for a known leaf count.
+fn hash_root(leaf_count: usize, tree_root: &str) -> String {
+    let mut data = vec![ROOT_PREFIX];
+    data.extend_from_slice(leaf_count.to_string().as_bytes());
+    data.push(b':');
+    data.extend_from_slice(tree_root.as_bytes());
+    hash_bytes(&data)
+}
+
+// One sibling on the path from a leaf to the root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub hash: String,
+    // True if the sibling sits on the left of the path node.
+    pub is_left: bool,
+}
+
+// Inclusion proof for the leaf at `index` of a tree with `leaf_count` leaves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub leaf_count: usize,
+    pub siblings: Vec<ProofStep>,
+}
+
+// Builds the next level of the tree. An unpaired last node is carried up
+// unchanged instead of being hashed with itself, so no two leaf lists share a root.
+fn next_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => single.clone(),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+// Computes the Merkle root over a list of leaf values (usually hashes).
+pub fn merkle_root(leaves: &[String]) -> String {
+    if leaves.is_empty() {
+        return EMPTY_ROOT.to_string();
+    }
+
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    hash_root(leaves.len(), &level[0])
+}
+
+// Produces the proof that `leaves[index]` is part of the tree.
+pub fn generate_proof(leaves: &[String], index: usize) -> Option<MerkleProof> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut position = index;
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            siblings.push(ProofStep {
+                hash: level[sibling].clone(),
+                is_left: sibling < position,
+            });
+        }
+        position /= 2;
+        level = next_level(&level);
+    }
+
+    Some(MerkleProof { index, leaf_count: leaves.len(), siblings })
+}
+
+// Checks that `leaf` is included at `proof.index` in the tree with the given root.
+// The path is walked from the index and leaf count, so every sibling must sit on the
+// side the index implies and the proof must hold exactly one sibling per paired level.
+pub fn verify_proof(leaf: &str, proof: &MerkleProof, root: &str) -> bool {
+    if proof.index >= proof.leaf_count {
+        return false;
+    }
+
+    let mut steps = proof.siblings.iter();
+    let mut current = hash_leaf(leaf);
+    let mut position = proof.index;
+    let mut width = proof.leaf_count;
+    while width > 1 {
+        let sibling = position ^ 1;
+        if sibling < width {
+            let Some(step) = steps.next() else {
+                return false;
+            };
+            if step.is_left != (sibling < position) {
+                return false;
+            }
+            current = if step.is_left {
+                hash_node(&step.hash, &current)
+            } else {
+                hash_node(&current, &step.hash)
+            };
+        }
+        position /= 2;
+        width = width.div_ceil(2);
+    }
+    steps.next().is_none() && hash_root(proof.leaf_count, &current) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("leaf{}", i)).collect()
+    }
+
+    #[test]
+    fn every_proof_verifies() {
+        for count in 1..=17 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = generate_proof(&leaves, index).unwrap();
+                assert!(verify_proof(leaf, &proof, &root), "leaf {} of {}", index, count);
+            }
+            assert!(generate_proof(&leaves, count).is_none());
+        }
+    }
+
+    #[test]
+    fn rejects_a_proof_for_another_leaf_or_root() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let proof = generate_proof(&leaves, 2).unwrap();
+        assert!(!verify_proof("leaf3", &proof, &root));
+        assert!(!verify_proof("leaf2", &proof, &merkle_root(&leaves[..4])));
+    }
+
+    #[test]
+    fn rejects_a_tampered_index_or_leaf_count() {
+        for count in 2..=9 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = generate_proof(&leaves, index).unwrap();
+                for other in (0..count + 2).filter(|other| *other != index) {
+                    let moved = MerkleProof { index: other, ..proof.clone() };
+                    assert!(!verify_proof(leaf, &moved, &root), "index {} as {} of {}", index, other, count);
+                }
+                for other in (1..count + 3).filter(|other| *other != count) {
+                    let resized = MerkleProof { leaf_count: other, ..proof.clone() };
+                    assert!(!verify_proof(leaf, &resized, &root), "{} leaves as {}", count, other);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn leaf_lists_do_not_share_roots() {
+        // An unpaired last leaf is carried up, so duplicating it must change the root
+        let three = leaves(3);
+        let mut four = three.clone();
+        four.push(three[2].clone());
+        assert_ne!(merkle_root(&three), merkle_root(&four));
+        assert_eq!(merkle_root(&[]), EMPTY_ROOT);
+    }
+}
@@ -2,3 +2,6 @@
 
 pub mod hash;
 pub mod keys; 
+pub mod merkle;
+pub mod signature;
+pub mod smt;
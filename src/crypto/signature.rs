@@ -0,0 +1,46 @@
+// src/crypto/signature.rs
+
+use crate::crypto::keys::{public_key_to_address, KeyPair};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, Secp256k1};
+
+// Turns a hex encoded SHA-256 digest (as produced by `calculate_hash`) into a signable message.
+fn message_from_hash(hash: &str) -> Option<Message> {
+    let bytes: [u8; 32] = hex::decode(hash).ok()?.try_into().ok()?;
+    Some(Message::from_digest(bytes))
+}
+
+// Signs a hex encoded digest with the given key pair.
+// The result is the hex encoding of the 64-byte compact signature followed by
+// the recovery id, so the signer's public key can be recovered from it.
+pub fn sign_hash(hash: &str, keypair: &KeyPair) -> String {
+    let message = message_from_hash(hash).expect("Digest must be a 32-byte hex string.");
+    let secp = Secp256k1::new();
+    let signature = secp.sign_ecdsa_recoverable(&message, &keypair.secret_key);
+    let (recovery_id, compact) = signature.serialize_compact();
+
+    let mut bytes = compact.to_vec();
+    bytes.push(recovery_id.to_i32() as u8);
+    hex::encode(bytes)
+}
+
+// Recovers the Erbium address that produced `signature` over the hex encoded digest.
+// Returns None if the digest or the signature are malformed.
+pub fn recover_address(hash: &str, signature: &str) -> Option<String> {
+    let message = message_from_hash(hash)?;
+    let bytes = hex::decode(signature).ok()?;
+    if bytes.len() != 65 {
+        return None;
+    }
+
+    let recovery_id = RecoveryId::from_i32(bytes[64] as i32).ok()?;
+    let signature = RecoverableSignature::from_compact(&bytes[..64], recovery_id).ok()?;
+    let secp = Secp256k1::new();
+    let public_key = secp.recover_ecdsa(&message, &signature).ok()?;
+    Some(public_key_to_address(&public_key))
+}
+
+// Checks that `signature` over the digest was produced by the owner of `address`.
+pub fn verify_signature(hash: &str, signature: &str, address: &str) -> bool {
+    recover_address(hash, signature).is_some_and(|recovered| recovered == address)
+}
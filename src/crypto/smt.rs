@@ -0,0 +1,306 @@
+// src/crypto/smt.rs
+
+use crate::crypto::hash::hash_bytes;
+use crate::crypto::merkle::EMPTY_ROOT;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+// Leaves and branches get different prefixes, like in `merkle`, so one can never be
+// passed off as the other.
+const LEAF_PREFIX: u8 = 0x00;
+const BRANCH_PREFIX: u8 = 0x01;
+
+// Depth of the tree: one level per bit of a key's path
+const PATH_BITS: usize = 256;
+
+type Path = [u8; 32];
+
+// Where a key lives in the tree: the SHA-256 of the key, read bit by bit from the root
+fn key_path(key: &str) -> Path {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+fn bit(path: &Path, depth: usize) -> bool {
+    path[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn hash_leaf(path: &Path, value_hash: &str) -> String {
+    let mut data = vec![LEAF_PREFIX];
+    data.extend_from_slice(hex::encode(path).as_bytes());
+    data.extend_from_slice(value_hash.as_bytes());
+    hash_bytes(&data)
+}
+
+fn hash_branch(left: &str, right: &str) -> String {
+    let mut data = vec![BRANCH_PREFIX];
+    data.extend_from_slice(left.as_bytes());
+    data.extend_from_slice(right.as_bytes());
+    hash_bytes(&data)
+}
+
+// A subtree holding a single key is stored as that leaf, wherever it sits, so the
+// tree only has as many levels as it takes to tell its keys apart. Branches always
+// hold at least two keys. This shape only depends on the set of keys, so neither does the root.
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Leaf { path: Path, value_hash: String, hash: String },
+    Branch { left: Arc<Node>, right: Arc<Node>, hash: String },
+}
+
+impl Node {
+    fn leaf(path: Path, value_hash: String) -> Arc<Node> {
+        let hash = hash_leaf(&path, &value_hash);
+        Arc::new(Node::Leaf { path, value_hash, hash })
+    }
+
+    // Joins two subtrees, collapsing the ones that no longer need a branch
+    fn branch(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+        match (&*left, &*right) {
+            (Node::Empty, Node::Empty) => Arc::new(Node::Empty),
+            (Node::Leaf { .. }, Node::Empty) => left,
+            (Node::Empty, Node::Leaf { .. }) => right,
+            _ => {
+                let hash = hash_branch(left.hash(), right.hash());
+                Arc::new(Node::Branch { left, right, hash })
+            }
+        }
+    }
+
+    fn hash(&self) -> &str {
+        match self {
+            Node::Empty => EMPTY_ROOT,
+            Node::Leaf { hash, .. } | Node::Branch { hash, .. } => hash,
+        }
+    }
+}
+
+fn insert(node: &Arc<Node>, path: Path, value_hash: String, depth: usize) -> Arc<Node> {
+    match &**node {
+        Node::Empty => Node::leaf(path, value_hash),
+        Node::Leaf { path: existing, .. } if *existing == path => Node::leaf(path, value_hash),
+        Node::Leaf { path: existing, .. } => split(Arc::clone(node), *existing, Node::leaf(path, value_hash), path, depth),
+        Node::Branch { left, right, .. } => {
+            if bit(&path, depth) {
+                Node::branch(Arc::clone(left), insert(right, path, value_hash, depth + 1))
+            } else {
+                Node::branch(insert(left, path, value_hash, depth + 1), Arc::clone(right))
+            }
+        }
+    }
+}
+
+// Pushes two leaves down until their paths differ
+fn split(existing: Arc<Node>, existing_path: Path, new: Arc<Node>, new_path: Path, depth: usize) -> Arc<Node> {
+    match (bit(&existing_path, depth), bit(&new_path, depth)) {
+        (false, false) => Node::branch(split(existing, existing_path, new, new_path, depth + 1), Arc::new(Node::Empty)),
+        (true, true) => Node::branch(Arc::new(Node::Empty), split(existing, existing_path, new, new_path, depth + 1)),
+        (false, true) => Node::branch(existing, new),
+        (true, false) => Node::branch(new, existing),
+    }
+}
+
+fn remove(node: &Arc<Node>, path: &Path, depth: usize) -> Arc<Node> {
+    match &**node {
+        Node::Leaf { path: existing, .. } if existing == path => Arc::new(Node::Empty),
+        Node::Empty | Node::Leaf { .. } => Arc::clone(node),
+        Node::Branch { left, right, .. } => {
+            if bit(path, depth) {
+                Node::branch(Arc::clone(left), remove(right, path, depth + 1))
+            } else {
+                Node::branch(remove(left, path, depth + 1), Arc::clone(right))
+            }
+        }
+    }
+}
+
+/// Authenticated map from keys to value hashes. Every key has a fixed position given by
+/// its hash, so a proof can show a key is absent as well as present. Updates only rehash
+/// the path to the changed key, and clones share every unchanged subtree.
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree {
+    root: Arc<Node>,
+}
+
+impl Default for SparseMerkleTree {
+    fn default() -> Self {
+        Self { root: Arc::new(Node::Empty) }
+    }
+}
+
+/// Path from the root towards a key's position: the sibling hashes top down, then what
+/// the path ends at. That is the key's own leaf, another key's leaf sharing the path so
+/// far, or nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseMerkleProof {
+    pub siblings: Vec<String>,
+    /// Hex path and value hash of the leaf the path ends at, if any
+    pub leaf: Option<(String, String)>,
+}
+
+impl SparseMerkleTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn root(&self) -> String {
+        self.root.hash().to_string()
+    }
+
+    /// Sets the value hash stored under `key`
+    pub fn insert(&mut self, key: &str, value_hash: String) {
+        self.root = insert(&self.root, key_path(key), value_hash, 0);
+    }
+
+    /// Removes `key` from the tree, if it is there
+    pub fn remove(&mut self, key: &str) {
+        self.root = remove(&self.root, &key_path(key), 0);
+    }
+
+    /// Proves what the tree stores under `key`, including that it stores nothing
+    pub fn prove(&self, key: &str) -> SparseMerkleProof {
+        let path = key_path(key);
+        let mut siblings = Vec::new();
+        let mut node = &self.root;
+        let mut depth = 0;
+        loop {
+            match &**node {
+                Node::Empty => return SparseMerkleProof { siblings, leaf: None },
+                Node::Leaf { path, value_hash, .. } => {
+                    return SparseMerkleProof {
+                        siblings,
+                        leaf: Some((hex::encode(path), value_hash.clone())),
+                    };
+                }
+                Node::Branch { left, right, .. } => {
+                    if bit(&path, depth) {
+                        siblings.push(left.hash().to_string());
+                        node = right;
+                    } else {
+                        siblings.push(right.hash().to_string());
+                        node = left;
+                    }
+                    depth += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Checks a proof from `prove`: that the tree with `root` stores `value_hash` under `key`,
+/// or nothing at all when `value_hash` is None
+pub fn verify_proof(root: &str, key: &str, value_hash: Option<&str>, proof: &SparseMerkleProof) -> bool {
+    let path = key_path(key);
+    let depth = proof.siblings.len();
+    if depth > PATH_BITS {
+        return false;
+    }
+
+    let terminal = match (&proof.leaf, value_hash) {
+        (None, None) => EMPTY_ROOT.to_string(),
+        (None, Some(_)) => return false,
+        (Some((leaf_path, leaf_value_hash)), _) => {
+            let Some(leaf_path) = hex::decode(leaf_path).ok().and_then(|bytes| Path::try_from(bytes).ok()) else {
+                return false;
+            };
+            match value_hash {
+                // Our own leaf, with the claimed value
+                Some(value_hash) if leaf_path != path || leaf_value_hash != value_hash => return false,
+                // Another key's leaf, which must sit where our path leads
+                None if leaf_path == path || (0..depth).any(|d| bit(&leaf_path, d) != bit(&path, d)) => return false,
+                _ => hash_leaf(&leaf_path, leaf_value_hash),
+            }
+        }
+    };
+
+    let computed = proof.siblings.iter().enumerate().rev().fold(terminal, |current, (d, sibling)| {
+        if bit(&path, d) {
+            hash_branch(sibling, &current)
+        } else {
+            hash_branch(&current, sibling)
+        }
+    });
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("account:{}", i)).collect()
+    }
+
+    fn value_hash(key: &str) -> String {
+        hash_bytes(key.as_bytes())
+    }
+
+    fn tree_of(keys: &[String]) -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new();
+        for key in keys {
+            tree.insert(key, value_hash(key));
+        }
+        tree
+    }
+
+    #[test]
+    fn root_does_not_depend_on_insertion_order() {
+        let keys = keys(64);
+        let mut reversed = SparseMerkleTree::new();
+        for key in keys.iter().rev() {
+            // Overwriting a value leaves no trace of the old one
+            reversed.insert(key, "stale".to_string());
+            reversed.insert(key, value_hash(key));
+        }
+        assert_eq!(tree_of(&keys).root(), reversed.root());
+    }
+
+    #[test]
+    fn removing_a_key_is_the_same_as_never_inserting_it() {
+        let keys = keys(64);
+        let mut tree = tree_of(&keys);
+        for key in keys.iter().step_by(2) {
+            tree.remove(key);
+        }
+        let remaining: Vec<String> = keys.iter().skip(1).step_by(2).cloned().collect();
+        assert_eq!(tree.root(), tree_of(&remaining).root());
+
+        for key in &remaining {
+            tree.remove(key);
+        }
+        assert_eq!(tree.root(), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn proves_membership() {
+        let keys = keys(64);
+        let tree = tree_of(&keys);
+        let root = tree.root();
+        for key in &keys {
+            let proof = tree.prove(key);
+            assert!(verify_proof(&root, key, Some(&value_hash(key)), &proof));
+            assert!(!verify_proof(&root, key, Some("other value"), &proof));
+            assert!(!verify_proof(&root, key, None, &proof));
+        }
+    }
+
+    #[test]
+    fn proves_non_membership() {
+        let keys = keys(64);
+        let absent = ["account:missing".to_string(), "validator:0".to_string()];
+        for tree in [SparseMerkleTree::new(), tree_of(&keys[..1]), tree_of(&keys)] {
+            let root = tree.root();
+            for key in &absent {
+                let proof = tree.prove(key);
+                assert!(verify_proof(&root, key, None, &proof));
+                assert!(!verify_proof(&root, key, Some(&value_hash(key)), &proof));
+            }
+        }
+
+        // A present key's proof cannot be passed off as another key's absence
+        let tree = tree_of(&keys);
+        let proof = tree.prove(&keys[0]);
+        assert!(!verify_proof(&tree.root(), &keys[0], None, &proof));
+    }
+}
@@ -11,15 +11,40 @@ pub mod smart_contracts;
 pub mod storage;
 pub mod utils;
 
+use crate::core::genesis;
+use crate::node::init;
 use crate::node::runner::Node;
 
 #[tokio::main]
 async fn main() {
-    println!("--- Initializing Erbium Node ---");
+    let args: Vec<String> = std::env::args().skip(1).collect();
     
-    // Create a new node instance. This will handle loading/creating the blockchain.
-    let node = Node::new();
-    
-    // Run the node. This will start the P2P service and run forever.
-    node.run().await;
+    match args.first().map(String::as_str) {
+        None | Some("run") => {
+            println!("--- Initializing Erbium Node ---");
+
+            // Without genesis validators nobody may propose block #1, so the node would wait forever
+            if genesis::spec().validators.is_empty() {
+                eprintln!("❌ {} has no validators. Create a network with `erbium-node init --chain-id <id> --validator <public key>:<stake>` first.", genesis::GENESIS_PATH);
+                std::process::exit(1);
+            }
+            
+            // Create a new node instance. This will handle loading/creating the blockchain.
+            let node = Node::new();
+            
+            // Run the node. This will start the P2P service and run forever.
+            node.run().await;
+        }
+        Some("init") => {
+            if let Err(e) = init::run_init(&args[1..]) {
+                eprintln!("❌ init failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some("keygen") => init::run_keygen(),
+        Some(_) => {
+            eprintln!("{}", init::USAGE);
+            std::process::exit(1);
+        }
+    }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 // src/node/config.rs
 
+use crate::storage::db::{PruningConfig, StorageMode};
 use serde::Deserialize;
 use std::fs;
 
@@ -7,6 +8,10 @@ use std::fs;
 pub struct Config {
     pub listen_address: String,
     pub bootstrap_nodes: Vec<String>,
+    #[serde(default)]
+    pub storage_mode: StorageMode,
+    #[serde(default)]
+    pub pruning: PruningConfig,
 }
 
 impl Config {
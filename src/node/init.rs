@@ -0,0 +1,169 @@
+// src/node/init.rs
+
+use crate::core::chain::Blockchain;
+use crate::core::consensus::forks::{ForkSchedule, Upgrade};
+use crate::core::genesis::{
+    ConsensusParams, GenesisAllocation, GenesisSpec, GenesisValidator, GENESIS_PATH,
+};
+use crate::crypto::keys::{public_key_to_address, KeyPair};
+use crate::storage::db::{PruningConfig, StorageMode, DB_PATH};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+/// Where the node's secret key is kept, so the validator address it is listed or staked
+/// under survives restarts
+pub const NODE_KEY_PATH: &str = "./node.key";
+
+pub const USAGE: &str = "\
+Usage:
+  erbium-node [run]       Run the node
+  erbium-node keygen      Create the node key if needed and print its public key
+  erbium-node init [options]
+      Write a new genesis spec to config/genesis.json and initialize the data directory
+      --chain-id <id>                 Network identifier (required)
+      --timestamp <unix seconds>      Genesis timestamp (default: now)
+      --block-time <seconds>          Seconds between blocks (default: 10)
+      --block-gas-limit <gas>         Gas limit of every block
+      --initial-base-fee <fee>        Base fee of the genesis block
+      --validator <public key>:<stake>   Genesis validator, repeatable (at least one)
+      --alloc <address>:<balance>        Genesis balance, repeatable
+      --fork <upgrade>:<height>          Activate a network upgrade, repeatable";
+
+/// Builds a genesis spec from the `init` command line options
+pub fn parse_genesis_args(args: &[String]) -> Result<GenesisSpec, String> {
+    let mut chain_id = None;
+    let mut timestamp = None;
+    let mut consensus = ConsensusParams::default();
+    let mut validators = Vec::new();
+    let mut allocations = Vec::new();
+    let mut forks = ForkSchedule::new();
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--chain-id" => chain_id = Some(parse_number(flag, value)?),
+            "--timestamp" => timestamp = Some(parse_number(flag, value)?),
+            "--block-time" => consensus.block_time = parse_number(flag, value)?,
+            "--block-gas-limit" => consensus.block_gas_limit = parse_number(flag, value)?,
+            "--initial-base-fee" => consensus.initial_base_fee = parse_number(flag, value)?,
+            "--validator" => {
+                let (public_key, stake) = split_pair(flag, value)?;
+                let bytes = hex::decode(public_key.trim_start_matches("0x"))
+                    .map_err(|_| format!("Validator public key {} is not hex", public_key))?;
+                let public_key = PublicKey::from_slice(&bytes)
+                    .map_err(|_| format!("Validator public key {} is not a valid secp256k1 key", public_key))?;
+                validators.push(GenesisValidator { address: public_key_to_address(&public_key), stake });
+            }
+            "--alloc" => {
+                let (address, balance) = split_pair(flag, value)?;
+                allocations.push(GenesisAllocation { address: address.to_string(), balance });
+            }
+            "--fork" => {
+                let (name, height) = split_pair(flag, value)?;
+                let upgrade: Upgrade = serde_json::from_value(serde_json::Value::String(name.to_string()))
+                    .map_err(|_| format!("Unknown network upgrade {}", name))?;
+                forks.insert(upgrade, height);
+            }
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    if validators.is_empty() {
+        return Err("At least one --validator is required".to_string());
+    }
+
+    let spec = GenesisSpec {
+        chain_id: chain_id.ok_or("--chain-id is required")?,
+        timestamp: timestamp.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        }),
+        allocations,
+        validators,
+        consensus,
+        forks,
+    };
+    spec.validate()?;
+    Ok(spec)
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got {}", flag, value))
+}
+
+// Splits `<text>:<number>` as used by --validator, --alloc and --fork
+fn split_pair<'a>(flag: &str, value: &'a str) -> Result<(&'a str, u64), String> {
+    let (text, number) = value
+        .rsplit_once(':')
+        .ok_or_else(|| format!("{} expects <value>:<amount>, got {}", flag, value))?;
+    Ok((text, parse_number(flag, number)?))
+}
+
+/// The `init` command: writes the genesis spec, creates the node key and initializes
+/// the database with the genesis block and state. Refuses to touch an existing database.
+pub fn run_init(args: &[String]) -> Result<(), String> {
+    let spec = parse_genesis_args(args)?;
+    if Path::new(DB_PATH).exists() {
+        return Err(format!("Data directory {} already exists", DB_PATH));
+    }
+
+    if let Some(dir) = Path::new(GENESIS_PATH).parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&spec).expect("Failed to serialize genesis spec.");
+    fs::write(GENESIS_PATH, json + "\n").map_err(|e| format!("Could not write {}: {}", GENESIS_PATH, e))?;
+    println!("📝 Wrote genesis spec for chain {} to {}", spec.chain_id, GENESIS_PATH);
+
+    let keypair = load_node_key();
+    // The storage mode does not matter yet: a new database only holds the genesis block
+    let chain = Blockchain::new(StorageMode::default(), PruningConfig::default());
+    println!("✅ Initialized {} with genesis block {}", DB_PATH, chain.blocks[0].calculate_hash());
+    println!("My Node ID (Address): {}", keypair.get_address());
+    Ok(())
+}
+
+/// The `keygen` command: prints the public key genesis validators are listed by
+pub fn run_keygen() {
+    let keypair = load_node_key();
+    println!("Address: {}", keypair.get_address());
+    println!("Public key: {}", hex::encode(keypair.public_key.serialize()));
+}
+
+/// Loads the node key from `NODE_KEY_PATH`, or generates one and stores it there readable
+/// by the owner only. A key file that group or others can read is refused rather than used.
+pub fn load_node_key() -> KeyPair {
+    if let Ok(metadata) = fs::metadata(NODE_KEY_PATH) {
+        let mode = metadata.permissions().mode();
+        if mode & 0o077 != 0 {
+            panic!(
+                "❌ CRITICAL: node key file {} is accessible by group or others (mode {:o}). Run `chmod 600 {}`.",
+                NODE_KEY_PATH,
+                mode & 0o777,
+                NODE_KEY_PATH
+            );
+        }
+        let secret_hex = fs::read_to_string(NODE_KEY_PATH).expect("Could not read node key file.");
+        let bytes = hex::decode(secret_hex.trim()).expect("Node key file is not valid hex.");
+        let secret_key = SecretKey::from_slice(&bytes).expect("Node key file holds an invalid key.");
+        let public_key = PublicKey::from_secret_key(&Secp256k1::new(), &secret_key);
+        return KeyPair { secret_key, public_key };
+    }
+
+    let keypair = KeyPair::new();
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(NODE_KEY_PATH)
+        .expect("Could not create node key file.");
+    file.write_all(hex::encode(keypair.secret_key.secret_bytes()).as_bytes())
+        .expect("Could not write node key file.");
+    println!("Generated new node key at {}", NODE_KEY_PATH);
+    keypair
+}
@@ -1,4 +1,5 @@
 // src/node/mod.rs
 
 pub mod runner;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod init;
\ No newline at end of file
@@ -1,107 +1,288 @@
 // src/node/runner.rs
 
 use crate::core::chain::Blockchain;
-use crate::core::consensus::edfm;
+use crate::core::consensus::limits::HEADER_SIZE_ALLOWANCE;
+use crate::core::consensus::tendermint::{Output, Proposal, Tendermint, Timeout};
+use crate::core::consensus::validator;
+use crate::core::consensus::vote::{Vote, VoteType};
+use crate::core::error::Error;
+use crate::core::genesis;
+use crate::core::mempool::Mempool;
+use crate::core::transaction::Transaction;
 use crate::crypto::keys::KeyPair;
 use crate::node::config::Config;
+use crate::node::init;
 use crate::p2p::message::P2pMessage;
-use crate::p2p::service::{connect_to_peers, listen_for_peers, PendingBlocks, PreCommits, PreVotes};
-use std::collections::HashMap;
+use crate::p2p::service::{connect_to_peers, listen_for_peers, SharedMempool};
+use crate::storage::db::{Storage, StorageMode};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::{broadcast, Mutex};
-use tokio::time::{interval, Duration};
+use tokio::select;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::{interval, Duration, Instant};
 
 pub type Tx = broadcast::Sender<P2pMessage>;
 
-/// Main block production loop - simplified version without complex consensus
-async fn block_producer_loop(
-    blockchain: Arc<Mutex<Blockchain>>, 
-    sender: Tx, 
-    node_address: String,
-) {
-    let mut interval = interval(Duration::from_secs(10));
-    
-    println!("⏰ Block producer started - checking every 10 seconds");
-    
-    // WAIT FOR INITIAL SYNC - Don't produce blocks immediately
-    println!("🔄 Waiting 15 seconds for initial network synchronization...");
-    tokio::time::sleep(Duration::from_secs(15)).await;
-    println!("✅ Starting block production after sync period");
+/// How often a pruned node sweeps its database for unreachable entries
+const GC_INTERVAL_SECS: u64 = 600;
+
+/// Background garbage collection of storage entries our chain no longer reaches.
+/// The sweep is blocking I/O over the whole database, so it runs on the blocking pool
+/// and without the chain lock; consensus keeps adding blocks meanwhile.
+async fn garbage_collection_loop(storage: Storage) {
+    let mut interval = interval(Duration::from_secs(GC_INTERVAL_SECS));
+    interval.tick().await; // The first tick completes immediately
     
     loop {
         interval.tick().await;
-        
-        let mut chain = blockchain.lock().await;
-        
-        // Only produce blocks if we have registered validators
-        if chain.state.validators.is_empty() {
-            println!("⏳ No validators yet, waiting...");
-            continue;
+        let storage = storage.clone();
+        match tokio::task::spawn_blocking(move || storage.collect_garbage()).await {
+            Ok(deleted) => println!("🧹 Garbage collection removed {} unreachable storage entries", deleted),
+            Err(e) => eprintln!("❌ Garbage collection failed: {}", e),
         }
-        
-        // DEBUG: Show current chain status
-        println!("📊 Current chain: {} blocks, last block #{}", 
-                 chain.blocks.len(), chain.blocks.last().unwrap().header.block_number);
-        
-        let last_block = chain.blocks.last().unwrap();
-        let seed = last_block.calculate_hash();
-        
-        // Select proposer based on EDFM consensus
-        if let Some(winner_address) = edfm::select_proposer(&seed, &chain.state.validators) {
-            if winner_address == node_address {
-                println!("\n🎯 IT'S OUR TURN! Creating block #{}", last_block.header.block_number + 1);
-                
-                let new_block = crate::core::block::Block::new(
-                    last_block.header.block_number + 1,
-                    last_block.calculate_hash(),
-                    vec![],
-                );
-                
-                // DEBUG: Show block details for troubleshooting
-                println!("📦 New block details - Prev Hash: {}, Number: {}", 
-                         &new_block.header.prev_block_hash[..8], new_block.header.block_number);
-                
-                // Add block to local chain first
-                if chain.add_block(new_block.clone()) {
-                    println!("✅ Block #{} added to local chain", new_block.header.block_number);
-                    
-                    // Broadcast simple block message to network
-                    let message = P2pMessage::NewBlock(new_block);
-                    if let Err(e) = sender.send(message) {
-                        eprintln!("❌ Failed to broadcast block: {}", e);
-                    } else {
-                        println!("📤 Block broadcasted to network");
-                    }
+    }
+}
+
+/// Inputs of the consensus engine that arrive from outside the consensus task
+#[derive(Debug)]
+pub enum ConsensusEvent {
+    Proposal(Box<Proposal>),
+    Vote(Vote),
+    Timeout(Timeout),
+}
+
+impl ConsensusEvent {
+    // Height a proposal or vote is for
+    fn height(&self) -> Option<u64> {
+        match self {
+            ConsensusEvent::Proposal(proposal) => Some(proposal.height),
+            ConsensusEvent::Vote(vote) => Some(vote.height),
+            ConsensusEvent::Timeout(_) => None,
+        }
+    }
+
+    // Validator that signed a proposal or vote, if the signature checks out
+    fn signer(&self) -> Option<String> {
+        match self {
+            ConsensusEvent::Proposal(proposal) => proposal.proposer(),
+            ConsensusEvent::Vote(vote) if vote.verify_signature() => Some(vote.validator.clone()),
+            ConsensusEvent::Vote(_) | ConsensusEvent::Timeout(_) => None,
+        }
+    }
+}
+
+pub type ConsensusTx = mpsc::UnboundedSender<ConsensusEvent>;
+
+/// Proposals and votes for the next height we keep from each validator until we reach it.
+/// Once a validator has sent more, its oldest ones are dropped.
+const MAX_FUTURE_EVENTS_PER_VALIDATOR: usize = 64;
+
+/// Feeds a proposal, vote or timeout to the engine. Proposed blocks are validated against our tip here.
+async fn dispatch_event(engine: &mut Tendermint, event: ConsensusEvent, blockchain: &Mutex<Blockchain>) -> Vec<Output> {
+    let result = match event {
+        ConsensusEvent::Proposal(proposal) => {
+            let chain = blockchain.lock().await;
+            let valid = proposal.block.header.block_number == chain.height() + 1
+                && validator::validate_block(&proposal.block, chain.blocks.last().unwrap(), &chain.state);
+            engine.on_proposal(*proposal, valid)
+        }
+        ConsensusEvent::Vote(vote) => engine.on_vote(vote),
+        ConsensusEvent::Timeout(timeout) => Ok(engine.on_timeout(timeout)),
+    };
+    result.unwrap_or_else(|e| {
+        println!("⚠️  Consensus message rejected: {}", e);
+        Vec::new()
+    })
+}
+
+/// Carries out what the engine asked for, feeding the results of block building back to it
+#[allow(clippy::too_many_arguments)]
+async fn handle_outputs(
+    engine: &mut Tendermint,
+    outputs: Vec<Output>,
+    blockchain: &Mutex<Blockchain>,
+    sender: &Tx,
+    mempool: &SharedMempool,
+    events_tx: &ConsensusTx,
+    keypair: &KeyPair,
+    next_height_at: &mut Instant,
+) {
+    let mut queue: VecDeque<Output> = outputs.into();
+    while let Some(output) = queue.pop_front() {
+        match output {
+            Output::BuildProposal { height, round } => {
+                let chain = blockchain.lock().await;
+                if chain.height() + 1 != height {
+                    continue;
+                }
+
+                // Pull the best paying, nonce-consistent transactions that fit in a block from the mempool
+                let transactions = {
+                    let context = chain.next_block_context(round);
+                    let mut pool = mempool.lock().await;
+                    pool.prune(&chain.state, context.block_number);
+                    pool.select_transactions(
+                        &chain.state,
+                        &context,
+                        genesis::block_gas_limit(),
+                        context.rules.max_block_size - HEADER_SIZE_ALLOWANCE,
+                    )
+                };
+                let block = chain.create_block(transactions, round, keypair);
+                println!("📦 Proposing block #{} in round {} with {} transactions", height, round, block.transactions.len());
+                queue.extend(engine.propose(block));
+            }
+            Output::Proposal(proposal) => {
+                if let Err(e) = sender.send(P2pMessage::ProposeBlock(proposal)) {
+                    eprintln!("❌ Failed to broadcast proposal: {}", e);
+                }
+            }
+            Output::Vote(vote) => {
+                let message = match vote.vote_type {
+                    VoteType::PreVote => P2pMessage::PreVote(vote),
+                    VoteType::PreCommit => P2pMessage::PreCommit(vote),
+                };
+                if let Err(e) = sender.send(message) {
+                    eprintln!("❌ Failed to broadcast vote: {}", e);
+                }
+            }
+            Output::ScheduleTimeout(timeout) => {
+                let events_tx = events_tx.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(timeout.duration).await;
+                    let _ = events_tx.send(ConsensusEvent::Timeout(timeout));
+                });
+            }
+            Output::Commit { block, round } => {
+                let mut chain = blockchain.lock().await;
+                let block_number = block.header.block_number;
+                if chain.height() + 1 != block_number {
+                    // The block already reached us through sync or a peer's NewBlock
+                    continue;
+                }
+                if !chain.add_block(block.clone()) {
+                    eprintln!("❌ Committed block #{} could not be added to the chain", block_number);
+                    continue;
+                }
+                chain.save_state();
+                mempool.lock().await.prune(&chain.state, chain.height() + 1);
+                println!("✅ Block #{} finalized with {} transactions", block_number, block.transactions.len());
+                *next_height_at = Instant::now() + Duration::from_secs(genesis::spec().consensus.block_time);
+
+                // The proposer announces the block to nodes that do not follow consensus messages
+                if engine.proposer(round) == Some(keypair.get_address())
+                    && let Err(e) = sender.send(P2pMessage::NewBlock(block))
+                {
+                    eprintln!("❌ Failed to broadcast block: {}", e);
                 }
-            } else {
-                println!("⏳ Not our turn - proposer is: {}", winner_address);
             }
         }
     }
 }
 
+/// Runs the consensus engine: feeds it peer messages and timeouts, and starts the next
+/// height `block_time` seconds after the previous block was committed
+async fn consensus_loop(
+    blockchain: Arc<Mutex<Blockchain>>,
+    sender: Tx,
+    mempool: SharedMempool,
+    keypair: Arc<KeyPair>,
+    events_tx: ConsensusTx,
+    mut events: mpsc::UnboundedReceiver<ConsensusEvent>,
+) {
+    let mut engine = Tendermint::new(Arc::clone(&keypair));
+    let mut future_events: HashMap<String, VecDeque<ConsensusEvent>> = HashMap::new();
+    let mut tick = interval(Duration::from_secs(1));
+
+    // WAIT FOR INITIAL SYNC - Don't take part in consensus immediately
+    println!("🔄 Waiting 15 seconds for initial network synchronization...");
+    tokio::time::sleep(Duration::from_secs(15)).await;
+    println!("✅ Starting consensus after sync period");
+    let mut next_height_at = Instant::now();
+
+    loop {
+        let outputs = select! {
+            Some(event) = events.recv() => {
+                match event.height() {
+                    Some(height) if height > engine.height() => {
+                        // Only keep what the next height's validators signed, most likely
+                        // the same ones as now; anything further ahead we catch up on by sync
+                        if height == engine.height() + 1
+                            && let Some(signer) = event.signer()
+                            && engine.validators().contains(&signer)
+                        {
+                            let queue = future_events.entry(signer).or_default();
+                            if queue.len() == MAX_FUTURE_EVENTS_PER_VALIDATOR {
+                                queue.pop_front();
+                            }
+                            queue.push_back(event);
+                        }
+                        Vec::new()
+                    }
+                    _ => dispatch_event(&mut engine, event, &blockchain).await,
+                }
+            }
+            _ = tick.tick() => {
+                // Start the next height once our tip moved past the one being decided,
+                // whether through our own commit, a peer's NewBlock or a sync
+                let (height, prev_block_hash, validators) = {
+                    let chain = blockchain.lock().await;
+                    (chain.height() + 1, chain.blocks.last().unwrap().calculate_hash(), chain.state.validators().clone())
+                };
+                if height == engine.height() || Instant::now() < next_height_at {
+                    continue;
+                }
+                if validators.is_empty() {
+                    println!("⏳ No validators yet, waiting...");
+                    continue;
+                }
+
+                println!("📊 Starting consensus for block #{} with {} validators (set {})", height, validators.len(), &validators.hash()[..8]);
+                let mut outputs = engine.start_height(height, prev_block_hash, validators);
+                let current: Vec<_> = std::mem::take(&mut future_events)
+                    .into_values()
+                    .flatten()
+                    .filter(|event| event.height() == Some(height))
+                    .collect();
+                for event in current {
+                    outputs.extend(dispatch_event(&mut engine, event, &blockchain).await);
+                }
+                outputs
+            }
+        };
+        handle_outputs(&mut engine, outputs, &blockchain, &sender, &mempool, &events_tx, &keypair, &mut next_height_at).await;
+    }
+}
+
 pub struct Node {
     blockchain: Arc<Mutex<Blockchain>>,
     broadcast_tx: Tx,
-    keypair: KeyPair,
-    pending_blocks: PendingBlocks,
-    pre_votes: PreVotes,
-    pre_commits: PreCommits,
+    keypair: Arc<KeyPair>,
+    mempool: SharedMempool,
 }
 
 impl Node {
     pub fn new() -> Self {
-        let keypair = KeyPair::new();
+        let keypair = init::load_node_key();
+        let config = Config::load();
         let (broadcast_tx, _) = broadcast::channel(32);
         
         Self {
-            blockchain: Arc::new(Mutex::new(Blockchain::new())),
+            blockchain: Arc::new(Mutex::new(Blockchain::new(config.storage_mode, config.pruning))),
             broadcast_tx,
-            keypair,
-            pending_blocks: Arc::new(Mutex::new(HashMap::new())),
-            pre_votes: Arc::new(Mutex::new(HashMap::new())),
-            pre_commits: Arc::new(Mutex::new(HashMap::new())),
+            keypair: Arc::new(keypair),
+            mempool: Arc::new(Mutex::new(Mempool::new())),
+        }
+    }
+
+    /// Submits a locally created transaction: validates it into the mempool and gossips it to peers
+    pub async fn submit_transaction(&self, tx: Transaction) -> Result<(), Error> {
+        let chain = self.blockchain.lock().await;
+        self.mempool.lock().await.add_transaction(tx.clone(), &chain.state, chain.height() + 1)?;
+        if let Err(e) = self.broadcast_tx.send(P2pMessage::NewTransaction(tx)) {
+            eprintln!("❌ Failed to broadcast transaction: {}", e);
         }
+        Ok(())
     }
 
     /// Main node initialization and execution function
@@ -110,43 +291,52 @@ impl Node {
         println!("My Node ID (Address): {}", self.keypair.get_address());
         let config = Config::load();
         
-        // Register as validator FIRST before starting P2P
+        // The validator set comes from the chain itself: genesis validators and stake transactions
         {
-            let mut chain = self.blockchain.lock().await;
-            if !chain.state.validators.contains_key(&self.keypair.get_address()) {
-                println!("👤 Registering as validator with stake 100000");
-                chain.state.register_validator(self.keypair.get_address().clone(), 100000);
-                chain.save_state();
+            let chain = self.blockchain.lock().await;
+            if chain.state.validators().contains(&self.keypair.get_address()) {
+                println!("👤 We are an active validator");
+            } else {
+                println!("👀 Not a validator - following the chain only");
             }
         }
         
-        // Start P2P services AFTER validator registration
+        // Peers hand proposals and votes to the consensus task through this channel
+        let (consensus_tx, consensus_rx) = mpsc::unbounded_channel();
+        
+        // Start P2P services
         let listen_task = listen_for_peers(
             config.listen_address.clone(),
             Arc::clone(&self.blockchain), 
             self.broadcast_tx.clone(),
-            Arc::clone(&self.pending_blocks), 
-            Arc::clone(&self.pre_votes),
-            Arc::clone(&self.pre_commits),
+            Arc::clone(&self.mempool),
+            consensus_tx.clone(),
         );
 
         let connect_task = connect_to_peers(
             config.bootstrap_nodes.clone(), 
             Arc::clone(&self.blockchain), 
             self.broadcast_tx.clone(),
-            Arc::clone(&self.pending_blocks), 
-            Arc::clone(&self.pre_votes),
-            Arc::clone(&self.pre_commits),
+            Arc::clone(&self.mempool),
+            consensus_tx.clone(),
         );
 
-        // Start simplified block producer
-        let producer_task = block_producer_loop(
-            Arc::clone(&self.blockchain), 
-            self.broadcast_tx.clone(), 
-            self.keypair.get_address(),
+        let consensus_task = consensus_loop(
+            Arc::clone(&self.blockchain),
+            self.broadcast_tx.clone(),
+            Arc::clone(&self.mempool),
+            Arc::clone(&self.keypair),
+            consensus_tx.clone(),
+            consensus_rx,
         );
 
+        // Only pruned nodes collect garbage; the others keep everything they ever stored
+        if config.storage_mode == StorageMode::Pruned {
+            let storage = self.blockchain.lock().await.storage();
+            tokio::spawn(garbage_collection_loop(storage));
+        }
+
         println!("🚀 Node started successfully!");
-        tokio::join!(producer_task, listen_task, connect_task);
+        tokio::join!(consensus_task, listen_task, connect_task);
     }
 }
\ No newline at end of file
@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 use crate::core::block::Block;
+use crate::core::consensus::tendermint::Proposal;
+use crate::core::consensus::vote::Vote;
+use crate::core::transaction::Transaction;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum P2pMessage {
@@ -10,16 +13,9 @@ pub enum P2pMessage {
     },
     RequestChain,
     RespondChain(Vec<Block>),
-    RegisterValidator {
-        address: String,
-        stake: u64,
-    },
-    ProposeBlock(Block),
+    ProposeBlock(Proposal),
     NewBlock(Block),
-    PreVote {
-        block_hash: String,
-    },
-    PreCommit {
-        block_hash: String,
-    },
+    NewTransaction(Transaction),
+    PreVote(Vote),
+    PreCommit(Vote),
 }
\ No newline at end of file
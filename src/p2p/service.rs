@@ -1,12 +1,10 @@
 // src/p2p/service.rs
 
-use crate::core::block::Block;
 use crate::core::chain::Blockchain;
-use crate::core::consensus::validator;
-use crate::node::runner::Tx;
+use crate::core::mempool::Mempool;
+use crate::node::runner::{ConsensusEvent, ConsensusTx, Tx};
 use crate::p2p::message::P2pMessage;
 use crate::p2p::ChainSynchronizer;
-use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -15,9 +13,7 @@ use tokio::select;
 use tokio::sync::{broadcast, Mutex};
 use tokio::time::Duration;
 
-pub type PendingBlocks = Arc<Mutex<HashMap<String, Block>>>;
-pub type PreVotes = Arc<Mutex<HashMap<String, HashSet<String>>>>;
-pub type PreCommits = Arc<Mutex<HashMap<String, HashSet<String>>>>;
+pub type SharedMempool = Arc<Mutex<Mempool>>;
 
 async fn handle_peer(
     socket: TcpStream,
@@ -25,9 +21,8 @@ async fn handle_peer(
     blockchain: Arc<Mutex<Blockchain>>,
     broadcast_tx: Tx,
     mut broadcast_rx: broadcast::Receiver<P2pMessage>,
-    pending_blocks: PendingBlocks,
-    pre_votes: PreVotes,
-    pre_commits: PreCommits,
+    mempool: SharedMempool,
+    consensus_tx: ConsensusTx,
 ) {
     println!("[{}] 🔄 Handling new peer connection", addr);
     let (reader, mut writer) = socket.into_split();
@@ -93,51 +88,9 @@ async fn handle_peer(
                                 println!("[{}] ⚠️  Chain synchronization not needed or failed", addr);
                             }
                         }
-                        P2pMessage::RegisterValidator { address, stake } => {
-                            println!("[{}] 👤 Registering validator: {}", addr, address);
-                            let mut bc = blockchain.lock().await;
-                            if !bc.state.validators.contains_key(&address) {
-                                bc.state.register_validator(address.clone(), stake);
-                                bc.save_state();
-                                
-                                // Forward validator registration to other peers
-                                match broadcast_tx.send(P2pMessage::RegisterValidator { address: address.clone(), stake }) {
-                                    Ok(_) => println!("[{}] 📤 Validator registration forwarded", addr),
-                                    Err(e) => eprintln!("[{}] ❌ Failed to forward validator registration: {}", addr, e),
-                                }
-                            } else {
-                                println!("[{}] ✅ Validator already registered: {}", addr, address);
-                            }
-                        }
-                        P2pMessage::ProposeBlock(block) => {
-                            let bc = blockchain.lock().await;
-                            let last_block = bc.blocks.last().unwrap();
-                            let expected_block_number = last_block.header.block_number + 1;
-                            
-                            println!("[{}] 📦 Received block proposal #{} (expected #{})", 
-                                     addr, block.header.block_number, expected_block_number);
-                            
-                            // Check if the proposed block is the next expected one
-                            if block.header.block_number == expected_block_number {
-                                if validator::validate_block(&block, last_block) {
-                                    let block_hash = block.calculate_hash();
-                                    println!("[{}] ✅ Valid block proposal #{} with hash {}", 
-                                             addr, block.header.block_number, &block_hash[..8]);
-                                    pending_blocks.lock().await.insert(block_hash.clone(), block);
-                                    
-                                    // Broadcast PreVote for this block
-                                    let block_hash_clone = block_hash.clone();
-                                    match broadcast_tx.send(P2pMessage::PreVote { block_hash: block_hash_clone }) {
-                                        Ok(_) => println!("[{}] ✅ PreVote broadcasted for block {}", addr, &block_hash[..8]),
-                                        Err(e) => eprintln!("[{}] ❌ Failed to broadcast PreVote: {}", addr, e),
-                                    }
-                                } else {
-                                    println!("[{}] ❌ Invalid block proposal #{} - validation failed", addr, block.header.block_number);
-                                }
-                            } else {
-                                println!("[{}] ⚠️  Out-of-order block proposal. Expected #{}, got #{}", 
-                                         addr, expected_block_number, block.header.block_number);
-                            }
+                        P2pMessage::ProposeBlock(proposal) => {
+                            println!("[{}] 📦 Received proposal for block #{} in round {}", addr, proposal.height, proposal.round);
+                            let _ = consensus_tx.send(ConsensusEvent::Proposal(Box::new(proposal)));
                         }
 
 P2pMessage::NewBlock(block) => {
@@ -178,59 +131,22 @@ P2pMessage::NewBlock(block) => {
     }
 }
 
-                        P2pMessage::PreVote { block_hash } => {
-                            let mut votes = pre_votes.lock().await;
-                            let entry = votes.entry(block_hash.clone()).or_insert_with(HashSet::new);
-                            if entry.insert(addr.to_string()) {
-                                let vote_count = entry.len();
-                                println!("[{}] ✅ Received NEW PreVote for block {}. Total: {}", addr, &block_hash[..8], vote_count);
-                                
-                                let total_validators = blockchain.lock().await.state.validators.len();
-                                let threshold = (total_validators * 2 / 3) + 1;
-                                
-                                if vote_count >= threshold {
-                                    println!("[{}] 🎯 PreVote threshold reached for block {} ({}/{})", 
-                                             addr, &block_hash[..8], vote_count, total_validators);
-                                    
-                                    // Broadcast PreCommit once threshold is reached
-                                    let block_hash_clone = block_hash.clone();
-                                    match broadcast_tx.send(P2pMessage::PreCommit { block_hash: block_hash_clone }) {
-                                        Ok(_) => println!("[{}] 📤 PreCommit broadcasted for block {}", addr, &block_hash[..8]),
-                                        Err(e) => eprintln!("[{}] ❌ Failed to broadcast PreCommit: {}", addr, e),
+                        P2pMessage::NewTransaction(tx) => {
+                            let bc = blockchain.lock().await;
+                            let tx_hash = tx.hash();
+                            match mempool.lock().await.add_transaction(tx.clone(), &bc.state, bc.height() + 1) {
+                                Ok(()) => {
+                                    // Forward newly seen transactions to other peers
+                                    match broadcast_tx.send(P2pMessage::NewTransaction(tx)) {
+                                        Ok(_) => println!("[{}] 📤 Transaction {} forwarded", addr, &tx_hash[..8]),
+                                        Err(e) => eprintln!("[{}] ❌ Failed to forward transaction: {}", addr, e),
                                     }
                                 }
+                                Err(e) => println!("[{}] ⚠️  Transaction {} rejected: {}", addr, &tx_hash[..8], e),
                             }
                         }
-                        P2pMessage::PreCommit { block_hash } => {
-                            let mut commits = pre_commits.lock().await;
-                            let entry = commits.entry(block_hash.clone()).or_insert_with(HashSet::new);
-                            if entry.insert(addr.to_string()) {
-                                let commit_count = entry.len();
-                                println!("[{}] ✅ Received NEW PreCommit for block {}. Total: {}", addr, &block_hash[..8], commit_count);
-                                
-                                let total_validators = blockchain.lock().await.state.validators.len();
-                                let threshold = (total_validators * 2 / 3) + 1;
-                                
-                                if commit_count >= threshold {
-                                    println!("[{}] 🎉 FINALIZING BLOCK {} (PreCommits: {}/{})", 
-                                             addr, &block_hash[..8], commit_count, total_validators);
-                                    
-                                    let mut bc = blockchain.lock().await;
-                                    if let Some(block) = pending_blocks.lock().await.remove(&block_hash) {
-                                        if bc.add_block(block) {
-                                            bc.save_state();
-                                            
-                                            // Clear consensus structures for next round
-                                            pre_votes.lock().await.clear();
-                                            pre_commits.lock().await.clear();
-                                            pending_blocks.lock().await.clear();
-                                            println!("[{}] 🧹 Consensus structures cleared for next round", addr);
-                                        }
-                                    } else {
-                                        println!("[{}] ⚠️  Block {} not found in pending blocks", addr, &block_hash[..8]);
-                                    }
-                                }
-                            }
+                        P2pMessage::PreVote(vote) | P2pMessage::PreCommit(vote) => {
+                            let _ = consensus_tx.send(ConsensusEvent::Vote(vote));
                         }
                     }
                 }
@@ -258,9 +174,8 @@ pub async fn listen_for_peers(
     address: String,
     blockchain: Arc<Mutex<Blockchain>>,
     broadcast_tx: Tx,
-    pending_blocks: PendingBlocks,
-    pre_votes: PreVotes,
-    pre_commits: PreCommits,
+    mempool: SharedMempool,
+    consensus_tx: ConsensusTx,
 ) {
     let listener = TcpListener::bind(&address).await.expect("Failed to bind to address");
     println!("🌐 P2P service listening on: {}", address);
@@ -270,8 +185,7 @@ pub async fn listen_for_peers(
                 println!("🔗 New incoming connection from: {}", addr);
                 tokio::spawn(handle_peer(
                     socket, addr, Arc::clone(&blockchain), broadcast_tx.clone(),
-                    broadcast_tx.subscribe(), Arc::clone(&pending_blocks),
-                    Arc::clone(&pre_votes), Arc::clone(&pre_commits),
+                    broadcast_tx.subscribe(), Arc::clone(&mempool), consensus_tx.clone(),
                 ));
             }
             Err(e) => {
@@ -285,9 +199,8 @@ pub async fn connect_to_peers(
     nodes: Vec<String>,
     blockchain: Arc<Mutex<Blockchain>>,
     broadcast_tx: Tx,
-    pending_blocks: PendingBlocks,
-    pre_votes: PreVotes,
-    pre_commits: PreCommits,
+    mempool: SharedMempool,
+    consensus_tx: ConsensusTx,
 ) {
     if nodes.is_empty() {
         println!("⚠️  No bootstrap nodes configured");
@@ -304,8 +217,7 @@ pub async fn connect_to_peers(
                 println!("✅ Successfully connected to peer: {}", node_addr);
                 tokio::spawn(handle_peer(
                     socket, addr, Arc::clone(&blockchain), broadcast_tx.clone(),
-                    broadcast_tx.subscribe(), Arc::clone(&pending_blocks),
-                    Arc::clone(&pre_votes), Arc::clone(&pre_commits),
+                    broadcast_tx.subscribe(), Arc::clone(&mempool), consensus_tx.clone(),
                 ));
             }
             Err(e) => {
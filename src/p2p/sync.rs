@@ -11,31 +11,26 @@ impl ChainSynchronizer {
     pub async fn should_sync_chain(blockchain: &Arc<Mutex<Blockchain>>) -> bool {
         let chain = blockchain.lock().await;
         // If we only have genesis block, we should sync
-        chain.blocks.len() <= 1
+        chain.height() == 0
     }
 
     pub async fn sync_chain(blockchain: Arc<Mutex<Blockchain>>, peer_blocks: Vec<Block>) -> bool {
-        if peer_blocks.is_empty() {
+        let Some(peer_tip) = peer_blocks.last().map(|block| block.header.block_number) else {
             return false;
-        }
+        };
 
         let mut chain = blockchain.lock().await;
         
         // Only sync if peer chain is longer and valid
-        if peer_blocks.len() > chain.blocks.len() {
-            println!("🔄 Syncing chain: local {} blocks -> peer {} blocks", 
-                     chain.blocks.len(), peer_blocks.len());
+        if peer_tip > chain.height() {
+            println!("🔄 Syncing chain: local tip #{} -> peer tip #{}", 
+                     chain.height(), peer_tip);
             
-            // Basic validation - check if genesis blocks match
-            if chain.blocks[0].calculate_hash() != peer_blocks[0].calculate_hash() {
-                eprintln!("❌ Genesis blocks don't match - cannot sync");
+            // Switch to the peer's chain, rolling back blocks after our last committed one if needed
+            if !chain.replace_chain_simple(peer_blocks) {
                 return false;
             }
-            
-            // Replace the entire chain
-            chain.blocks = peer_blocks;
-            chain.save_state();
-            println!("✅ Chain synchronized to {} blocks", chain.blocks.len());
+            println!("✅ Chain synchronized to block #{}", chain.height());
             true
         } else {
             false
@@ -1,16 +1,58 @@
 // src/storage/db.rs
 
-use crate::core::block::Block;
+use crate::core::block::{Block, Header};
+use crate::core::receipt::Receipt;
 use crate::core::state::State;
-use rocksdb::{DB, Options};
+use rocksdb::{DB, Options, WriteBatch};
+use serde::Deserialize;
 use std::fmt; // Import the fmt module
+use std::sync::Arc;
 
-const DB_PATH: &str = "./database";
+pub const DB_PATH: &str = "./database";
 const TIP_KEY: &str = "tip";
-const STATE_KEY: &str = "state";
+pub(crate) const STATE_PREFIX: &str = "state:";
+pub(crate) const RECEIPT_PREFIX: &str = "receipt:";
+pub(crate) const HEADER_PREFIX: &str = "header:";
+pub(crate) const HEIGHT_PREFIX: &str = "height:";
 
+/// How much state history a node keeps on disk
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageMode {
+    /// State snapshots only for recent blocks, enough to roll back a reorg
+    #[default]
+    Full,
+    /// State snapshots for every block, so any past height can be queried
+    Archive,
+    /// Only the most recent blocks and checkpoints keep their bodies and state,
+    /// older blocks are reduced to their headers. See `PruningConfig`.
+    Pruned,
+}
+
+/// Retention settings for `StorageMode::Pruned`
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct PruningConfig {
+    /// Number of most recent blocks that keep their full body and state
+    pub keep_recent: usize,
+    /// Every block whose number is a multiple of this is kept in full as a checkpoint
+    pub checkpoint_interval: u64,
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        Self {
+            keep_recent: 1_024,
+            checkpoint_interval: 10_000,
+        }
+    }
+}
+
+/// Handle to the node database. Clones share the same database, so a copy can be handed
+/// to background work such as garbage collection.
+#[derive(Clone)]
 pub struct Storage {
-    db: Option<DB>,
+    db: Option<Arc<DB>>,
 }
 
 // This manual implementation of Debug is now syntactically correct.
@@ -30,7 +72,7 @@ impl Storage {
         match DB::open(&opts, DB_PATH) {
             Ok(db) => {
                 println!("Successfully opened database at {}", DB_PATH);
-                Self { db: Some(db) }
+                Self { db: Some(Arc::new(db)) }
             },
             Err(e) => {
                 println!("Failed to open database: {}", e);
@@ -39,12 +81,67 @@ impl Storage {
         }
     }
 
+    /// Writes a block of the canonical chain and makes it the tip. The body, its height
+    /// index and the tip go in one write, so no reader ever sees a body that is not yet
+    /// indexed, which garbage collection would take for an abandoned fork.
     pub fn write_block(&self, block: &Block) {
         if let Some(db) = &self.db {
             let block_hash = block.calculate_hash();
-            let block_json = serde_json::to_string(block).unwrap();
-            db.put(block_hash.as_bytes(), block_json.as_bytes()).unwrap();
-            db.put(TIP_KEY.as_bytes(), block_hash.as_bytes()).unwrap();
+            let mut batch = WriteBatch::default();
+            batch.put(block_hash.as_bytes(), serde_json::to_string(block).unwrap());
+            batch.put(format!("{}{}", HEIGHT_PREFIX, block.header.block_number), block_hash.as_bytes());
+            batch.put(TIP_KEY.as_bytes(), block_hash.as_bytes());
+            db.write(batch).unwrap();
+        }
+    }
+
+    /// Records `block_hash` as the canonical block at `block_number`
+    pub fn write_height_index(&self, block_number: u64, block_hash: &str) {
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", HEIGHT_PREFIX, block_number);
+            db.put(key.as_bytes(), block_hash.as_bytes()).unwrap();
+        }
+    }
+
+    /// Hash of the canonical block at `block_number`
+    pub fn get_block_hash(&self, block_number: u64) -> Option<String> {
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", HEIGHT_PREFIX, block_number);
+            if let Ok(Some(bytes)) = db.get(key.as_bytes()) {
+                return String::from_utf8(bytes).ok();
+            }
+        }
+        None
+    }
+
+    /// Header of the block with `hash`, whether its body is still stored or it has been pruned
+    pub fn read_header(&self, hash: &str) -> Option<Header> {
+        if let Some(block) = self.read_block(hash) {
+            return Some(block.header);
+        }
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", HEADER_PREFIX, hash);
+            if let Ok(Some(bytes)) = db.get(key.as_bytes())
+                && let Ok(json) = String::from_utf8(bytes)
+            {
+                return serde_json::from_str(&json).ok();
+            }
+        }
+        None
+    }
+
+    /// Reduces a block to its header: drops its body, state snapshot and receipts in one write
+    pub fn prune_block(&self, block: &Block) {
+        if let Some(db) = &self.db {
+            let block_hash = block.calculate_hash();
+            let mut batch = WriteBatch::default();
+            batch.put(format!("{}{}", HEADER_PREFIX, block_hash), serde_json::to_string(&block.header).unwrap());
+            batch.delete(block_hash.as_bytes());
+            batch.delete(format!("{}{}", STATE_PREFIX, block_hash));
+            for tx in &block.transactions {
+                batch.delete(format!("{}{}", RECEIPT_PREFIX, tx.hash()));
+            }
+            db.write(batch).unwrap();
         }
     }
 
@@ -68,24 +165,74 @@ impl Storage {
         None
     }
 
-    pub fn write_state(&self, state: &State) {
+    /// Stores the state as it is after applying the block with `block_hash`
+    pub fn write_state(&self, block_hash: &str, state: &State) {
         if let Some(db) = &self.db {
+            let key = format!("{}{}", STATE_PREFIX, block_hash);
             let state_json = serde_json::to_string(state).unwrap();
-            db.put(STATE_KEY.as_bytes(), state_json.as_bytes()).unwrap();
-            println!("DEBUG: Chain state has been persisted to disk.");
+            db.put(key.as_bytes(), state_json.as_bytes()).unwrap();
+            println!("DEBUG: Chain state after block {} has been persisted to disk.", &block_hash[..8]);
         }
     }
 
-    pub fn read_state(&self) -> Option<State> {
+    /// Reads the state as it was after applying the block with `block_hash`
+    pub fn read_state(&self, block_hash: &str) -> Option<State> {
         if let Some(db) = &self.db {
-            if let Ok(Some(bytes)) = db.get(STATE_KEY.as_bytes()) {
-                if let Ok(json) = String::from_utf8(bytes) {
-                    println!("DEBUG: Found and loaded chain state from disk.");
-                    return serde_json::from_str(&json).ok();
-                }
+            let key = format!("{}{}", STATE_PREFIX, block_hash);
+            if let Ok(Some(bytes)) = db.get(key.as_bytes())
+                && let Ok(json) = String::from_utf8(bytes)
+            {
+                return serde_json::from_str(&json).ok();
             }
         }
-        println!("DEBUG: No chain state found on disk. Creating a new one.");
         None
     }
-}
\ No newline at end of file
+
+    pub fn delete_state(&self, block_hash: &str) {
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", STATE_PREFIX, block_hash);
+            db.delete(key.as_bytes()).unwrap();
+        }
+    }
+
+    pub fn write_receipts(&self, receipts: &[Receipt]) {
+        if let Some(db) = &self.db {
+            for receipt in receipts {
+                let key = format!("{}{}", RECEIPT_PREFIX, receipt.tx_hash);
+                let receipt_json = serde_json::to_string(receipt).unwrap();
+                db.put(key.as_bytes(), receipt_json.as_bytes()).unwrap();
+            }
+        }
+    }
+
+    /// Removes the receipts of the transactions in `block`, e.g. when it leaves the chain
+    pub fn delete_receipts(&self, block: &Block) {
+        if let Some(db) = &self.db {
+            for tx in &block.transactions {
+                let key = format!("{}{}", RECEIPT_PREFIX, tx.hash());
+                db.delete(key.as_bytes()).unwrap();
+            }
+        }
+    }
+
+    pub fn read_receipt(&self, tx_hash: &str) -> Option<Receipt> {
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", RECEIPT_PREFIX, tx_hash);
+            if let Ok(Some(bytes)) = db.get(key.as_bytes())
+                && let Ok(json) = String::from_utf8(bytes)
+            {
+                return serde_json::from_str(&json).ok();
+            }
+        }
+        None
+    }
+
+    /// Deletes entries no longer reachable from the canonical chain, see `storage::rocksdb`.
+    /// Sweeps the whole database, so run it off the async workers.
+    pub fn collect_garbage(&self) -> usize {
+        match &self.db {
+            Some(db) => super::rocksdb::collect_garbage(db),
+            None => 0,
+        }
+    }
+}
@@ -1,2 +1,3 @@
 // src/storage/mod.rs
-pub mod db;
\ No newline at end of file
+pub mod db;
+pub mod rocksdb;
\ No newline at end of file
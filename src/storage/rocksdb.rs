@@ -0,0 +1,93 @@
+// src/storage/rocksdb.rs
+
+use super::db::{HEADER_PREFIX, HEIGHT_PREFIX, RECEIPT_PREFIX, STATE_PREFIX};
+use crate::core::block::{Block, Header};
+use crate::core::receipt::Receipt;
+use ::rocksdb::{IteratorMode, WriteBatch, DB};
+use std::collections::{HashMap, HashSet};
+
+/// Sweeps the whole database and deletes everything the canonical chain (the `height:` index)
+/// no longer reaches: bodies and headers of abandoned forks, their state snapshots, and
+/// receipts of transactions that are not in a stored canonical block.
+/// Works on a snapshot, so the node keeps writing blocks while it runs.
+/// Returns the number of deleted entries.
+pub fn collect_garbage(db: &DB) -> usize {
+    let mut canonical: HashMap<u64, String> = HashMap::new();
+    let mut blocks: Vec<(String, u64, HashSet<String>)> = Vec::new();
+    let mut headers: Vec<(String, u64)> = Vec::new();
+    let mut states: Vec<String> = Vec::new();
+    let mut receipts: Vec<(String, u64)> = Vec::new();
+
+    let snapshot = db.snapshot();
+    for entry in snapshot.iterator(IteratorMode::Start) {
+        let Ok((key, value)) = entry else { continue };
+        let Ok(key) = String::from_utf8(key.to_vec()) else { continue };
+
+        if let Some(number) = key.strip_prefix(HEIGHT_PREFIX) {
+            if let (Ok(number), Ok(hash)) = (number.parse(), String::from_utf8(value.to_vec())) {
+                canonical.insert(number, hash);
+            }
+        } else if let Some(hash) = key.strip_prefix(HEADER_PREFIX) {
+            if let Ok(header) = serde_json::from_slice::<Header>(&value) {
+                headers.push((hash.to_string(), header.block_number));
+            }
+        } else if let Some(hash) = key.strip_prefix(STATE_PREFIX) {
+            states.push(hash.to_string());
+        } else if let Some(tx_hash) = key.strip_prefix(RECEIPT_PREFIX) {
+            if let Ok(receipt) = serde_json::from_slice::<Receipt>(&value) {
+                receipts.push((tx_hash.to_string(), receipt.block_number));
+            }
+        } else if let Ok(block) = serde_json::from_slice::<Block>(&value) {
+            // Block bodies are stored under their bare hash
+            let tx_hashes = block.transactions.iter().map(|tx| tx.hash()).collect();
+            blocks.push((key, block.header.block_number, tx_hashes));
+        }
+    }
+
+    let is_canonical = |hash: &str, number: u64| canonical.get(&number).is_some_and(|h| h == hash);
+    let canonical_hashes: HashSet<&String> = canonical.values().collect();
+    // Entries written after the snapshot are not in it and so never deleted. Entries at a
+    // height whose canonical block changed since are left for the next sweep.
+    let settled = |number: u64| {
+        let live = db.get(format!("{}{}", HEIGHT_PREFIX, number)).ok().flatten();
+        live.as_deref() == canonical.get(&number).map(|hash| hash.as_bytes())
+    };
+    let numbers: HashMap<&str, u64> = blocks
+        .iter()
+        .map(|(hash, number, _)| (hash.as_str(), *number))
+        .chain(headers.iter().map(|(hash, number)| (hash.as_str(), *number)))
+        .collect();
+    let mut batch = WriteBatch::default();
+    let mut canonical_txs: HashMap<u64, &HashSet<String>> = HashMap::new();
+    let mut deleted = 0;
+
+    for (hash, number, tx_hashes) in &blocks {
+        if is_canonical(hash, *number) {
+            canonical_txs.insert(*number, tx_hashes);
+        } else if settled(*number) {
+            batch.delete(hash.as_bytes());
+            deleted += 1;
+        }
+    }
+    for (hash, number) in &headers {
+        if !is_canonical(hash, *number) && settled(*number) {
+            batch.delete(format!("{}{}", HEADER_PREFIX, hash));
+            deleted += 1;
+        }
+    }
+    for hash in &states {
+        if !canonical_hashes.contains(hash) && numbers.get(hash.as_str()).is_none_or(|number| settled(*number)) {
+            batch.delete(format!("{}{}", STATE_PREFIX, hash));
+            deleted += 1;
+        }
+    }
+    for (tx_hash, number) in &receipts {
+        if !canonical_txs.get(number).is_some_and(|txs| txs.contains(tx_hash)) && settled(*number) {
+            batch.delete(format!("{}{}", RECEIPT_PREFIX, tx_hash));
+            deleted += 1;
+        }
+    }
+
+    db.write(batch).unwrap();
+    deleted
+}